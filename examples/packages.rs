@@ -12,7 +12,7 @@ async fn main() -> Result<(), MainError> {
     let client = PostNL::new()?
         .login(
             env.get("USERNAME").expect("username not set"),
-            env.get("PASSWORD").expect("password not set"),
+            env.get("PASSWORD").expect("password not set").clone(),
         )
         .await?;
 
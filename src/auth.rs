@@ -1,6 +1,5 @@
 use crate::{Error, Result};
 use chrono::{DateTime, Duration, Utc};
-use parse_display::Display;
 use rand::Rng;
 use reqwest::redirect::Policy;
 use reqwest::{Client, Response};
@@ -8,14 +7,18 @@ use serde::export::{PhantomData, TryFrom};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::fmt;
 use std::str::FromStr;
+use tracing::{debug, field::Empty, instrument, Span};
 use url::Url;
+use zeroize::{Zeroize, Zeroizing};
 
 static LOGIN_URL: &str = "https://jouw.postnl.nl/identity/Account/Login";
 static AUTHORIZE_URL: &str = "https://jouw.postnl.nl/identity/connect/authorize";
 static TOKEN_URL: &str = "https://jouw.postnl.nl/identity/connect/token";
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Zeroize)]
+#[zeroize(drop)]
 struct RawToken {
     access_token: String,
     id_token: String,
@@ -34,11 +37,39 @@ enum RawTokenResponse {
     Ok(RawToken),
 }
 
-#[derive(Display, Clone, Debug, Serialize, Deserialize)]
-pub struct AccessToken(String);
+#[derive(Clone, Serialize, Deserialize, Zeroize)]
+#[zeroize(drop)]
+pub struct AccessToken(pub(crate) String);
+
+impl AccessToken {
+    /// Get the raw bearer token
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AccessToken([redacted])")
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, Zeroize)]
+#[zeroize(drop)]
+pub struct RefreshToken(pub(crate) String);
+
+impl RefreshToken {
+    /// Get the raw token
+    pub fn expose_secret(&self) -> &str {
+        &self.0
+    }
+}
 
-#[derive(Display, Clone, Debug, Serialize, Deserialize)]
-pub struct RefreshToken(String);
+impl fmt::Debug for RefreshToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RefreshToken([redacted])")
+    }
+}
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Token {
@@ -58,9 +89,9 @@ impl TryFrom<RawTokenResponse> for Token {
 
     fn try_from(raw: RawTokenResponse) -> Result<Self> {
         match raw {
-            RawTokenResponse::Ok(token) => Ok(Token {
-                access: AccessToken(token.access_token),
-                id_token: RefreshToken(token.id_token),
+            RawTokenResponse::Ok(mut token) => Ok(Token {
+                access: AccessToken(std::mem::take(&mut token.access_token)),
+                id_token: RefreshToken(std::mem::take(&mut token.id_token)),
                 expires: Utc::now() + Duration::seconds(token.expires_in - 15),
             }),
             RawTokenResponse::Error(err) => Err(Error::FailedToken(err.error)),
@@ -96,7 +127,15 @@ impl AuthHandler<New> {
         })
     }
 
-    pub async fn login(self, username: &str, password: &str) -> Result<AuthHandler<LoggedIn>> {
+    #[instrument(skip(self, username, password))]
+    pub async fn login(
+        self,
+        username: &str,
+        password: Zeroizing<String>,
+    ) -> Result<AuthHandler<LoggedIn>> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::LOGIN_ATTEMPTS.inc();
+
         let verification_token = self.verify_login().await?;
         self.do_login(username, password, &verification_token)
             .await?;
@@ -108,8 +147,10 @@ impl AuthHandler<New> {
     }
 
     /// Get the info needed to verify that we are "not a bot"
+    #[instrument(skip(self), fields(status = Empty))]
     async fn get_request_verification_info(&self) -> Result<VerificationInfo> {
         let response: Response = self.client.get(LOGIN_URL).send().await?;
+        Span::current().record("status", &response.status().as_u16());
         let body = response.text().await?;
 
         let request_token_regex =
@@ -134,6 +175,7 @@ impl AuthHandler<New> {
     }
 
     /// "Proof" that we are "not a bot", returning the verification token
+    #[instrument(skip(self), fields(url = Empty, status = Empty))]
     async fn verify_login(&self) -> Result<String> {
         let verification_info = self.get_request_verification_info().await?;
         let random_sensor_data = hex_random(22);
@@ -143,12 +185,11 @@ impl AuthHandler<New> {
             include_str!("sensordata.txt")
         );
 
-        let response: Response = self
-            .client
-            .post(&format!("https://jouw.postnl.nl/{}", verification_info.url))
-            .body(data)
-            .send()
-            .await?;
+        let url = format!("https://jouw.postnl.nl/{}", verification_info.url);
+        Span::current().record("url", &url.as_str());
+
+        let response: Response = self.client.post(&url).body(data).send().await?;
+        Span::current().record("status", &response.status().as_u16());
 
         let result: ValidateResponse = response.json().await?;
         if !result.success {
@@ -162,31 +203,38 @@ impl AuthHandler<New> {
     }
 
     /// Send the actual login request, setting the cookies
+    ///
+    /// Our own copy of the password is zeroized once the form is built, the copy in the request body
+    /// owned by reqwest is not.
+    #[instrument(skip(self, username, password, verification_token), fields(status = Empty, redirect = Empty))]
     async fn do_login(
         &self,
         username: &str,
-        password: &str,
+        password: Zeroizing<String>,
         verification_token: &str,
     ) -> Result<()> {
-        let response: Response = self
-            .client
-            .post(LOGIN_URL)
-            .form(&[
-                ("__RequestVerificationToken", verification_token),
-                ("ReturnUrl", ""),
-                ("Username", &username),
-                ("Password", &password),
-            ])
-            .send()
-            .await?;
+        let request = self.client.post(LOGIN_URL).form(&[
+            ("__RequestVerificationToken", verification_token),
+            ("ReturnUrl", ""),
+            ("Username", username),
+            ("Password", password.as_str()),
+        ]);
+        drop(password);
+
+        let response: Response = request.send().await?;
+        Span::current().record("status", &response.status().as_u16());
 
         if let Some(location_header) = get_redirect_url(&response) {
+            Span::current().record("redirect", &redact_url(&location_header).as_str());
             if location_header
                 .query_pairs()
                 .find(|(key, _)| key == "botdetected")
                 .map(|(_, value)| bool::from_str(value.as_ref()).unwrap_or_default())
                 == Some(true)
             {
+                #[cfg(feature = "metrics")]
+                crate::metrics::BLOCKED.inc();
+
                 return Err(Error::Blocked);
             }
         }
@@ -196,7 +244,11 @@ impl AuthHandler<New> {
 }
 
 impl AuthHandler<LoggedIn> {
+    #[instrument(skip(self))]
     pub async fn generate_token(&self) -> Result<Token> {
+        #[cfg(feature = "metrics")]
+        crate::metrics::TOKEN_REFRESHES.inc();
+
         let code = self
             .do_authorization(AuthorizationParams::new(), false)
             .await?;
@@ -205,6 +257,7 @@ impl AuthHandler<LoggedIn> {
     }
 
     /// Get the authorization code using the stored login cookies
+    #[instrument(skip(self, auth_params), fields(status = Empty, redirect = Empty))]
     async fn do_authorization(
         &self,
         auth_params: AuthorizationParams,
@@ -227,13 +280,16 @@ impl AuthHandler<LoggedIn> {
             ])
             .send()
             .await?;
+        Span::current().record("status", &response.status().as_u16());
 
         let location_header = get_redirect_url(&response)
             .ok_or(Error::AuthorizationFailure("No or invalid redirect url"))?;
+        Span::current().record("redirect", &redact_url(&location_header).as_str());
 
         let mut location_query_pairs = location_header.query_pairs().collect::<HashMap<_, _>>();
 
         if let Some(err) = location_query_pairs.remove("error") {
+            debug!(error = %err, "authorization failed");
             return Err(Error::VerificationFailure(err.to_string()));
         }
 
@@ -243,11 +299,12 @@ impl AuthHandler<LoggedIn> {
 
         Ok(AuthorizationCode {
             code: code.to_string(),
-            code_verifier: auth_params.code_verifier,
+            code_verifier: auth_params.code_verifier.clone(),
         })
     }
 
     /// Get the auth token using the authorization code
+    #[instrument(skip(self, code), fields(status = Empty))]
     async fn get_token_from_code(&self, code: AuthorizationCode) -> Result<RawTokenResponse> {
         let response: Response = self
             .client
@@ -261,17 +318,22 @@ impl AuthHandler<LoggedIn> {
             ])
             .send()
             .await?;
+        Span::current().record("status", &response.status().as_u16());
 
         Ok(response.json().await?)
     }
 }
 
+#[derive(Zeroize)]
+#[zeroize(drop)]
 struct AuthorizationParams {
     code_verifier: String,
     state: String,
     code_challenge: String,
 }
 
+#[derive(Zeroize)]
+#[zeroize(drop)]
 struct AuthorizationCode {
     code: String,
     code_verifier: String,
@@ -323,3 +385,84 @@ fn get_redirect_url(response: &Response) -> Option<Url> {
         .and_then(|header| header.to_str().ok())
         .and_then(|header_str| Url::parse(header_str).ok())
 }
+
+/// Query parameters that contain secrets and should not end up in logs
+static SENSITIVE_PARAMS: &[&str] = &[
+    "code",
+    "code_verifier",
+    "code_challenge",
+    "state",
+    "access_token",
+    "id_token",
+    "__RequestVerificationToken",
+];
+
+/// Format an url for logging with all secret query parameters redacted
+pub(crate) fn redact_url(url: &Url) -> String {
+    let mut redacted = url.clone();
+    if let Some(query) = url.query() {
+        redacted.set_query(Some(&redact_query(query)));
+    }
+    redacted.to_string()
+}
+
+/// Redact the secret parameters of a query, including those of urls nested in parameters like `ReturnUrl`
+fn redact_query(query: &str) -> String {
+    let pairs = url::form_urlencoded::parse(query.as_bytes()).map(|(key, value)| {
+        let value = if SENSITIVE_PARAMS.contains(&key.as_ref()) {
+            "[redacted]".to_string()
+        } else {
+            match value.find('?') {
+                Some(index) => format!("{}?{}", &value[..index], redact_query(&value[index + 1..])),
+                None => value.into_owned(),
+            }
+        };
+        (key.into_owned(), value)
+    });
+
+    url::form_urlencoded::Serializer::new(String::new())
+        .extend_pairs(pairs)
+        .finish()
+}
+
+#[test]
+fn test_redact_debug() {
+    let token = Token {
+        access: AccessToken("secret-access".to_string()),
+        id_token: RefreshToken("secret-id".to_string()),
+        expires: Utc::now(),
+    };
+
+    let debug = format!("{:?}", token);
+    assert!(!debug.contains("secret"));
+    assert!(debug.contains("AccessToken([redacted])"));
+    assert_eq!("secret-access", token.access.expose_secret());
+}
+
+#[test]
+fn test_redact_url() {
+    let url =
+        Url::parse("https://jouw.postnl.nl/silent-renew.html?code=secret&scope=openid&state=abc")
+            .unwrap();
+
+    assert_eq!(
+        "https://jouw.postnl.nl/silent-renew.html?code=%5Bredacted%5D&scope=openid&state=%5Bredacted%5D",
+        redact_url(&url)
+    );
+}
+
+#[test]
+fn test_redact_nested_url() {
+    let mut url = Url::parse("https://jouw.postnl.nl/identity/Account/Login").unwrap();
+    url.query_pairs_mut().append_pair(
+        "ReturnUrl",
+        "/connect/authorize/callback?client_id=pwb-web&code_challenge=secret&state=abc",
+    );
+
+    let redacted = Url::parse(&redact_url(&url)).unwrap();
+    let (_, return_url) = redacted.query_pairs().next().unwrap();
+    assert_eq!(
+        "/connect/authorize/callback?client_id=pwb-web&code_challenge=%5Bredacted%5D&state=%5Bredacted%5D",
+        return_url
+    );
+}
@@ -0,0 +1,30 @@
+use postnl::{IcalServer, PostNL};
+use std::env;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Serve the expected deliveries as a subscribable calendar
+///
+/// Configured with the `POSTNL_USERNAME`, `POSTNL_PASSWORD`, `LISTEN`, `ICAL_PATH` and `CACHE_TTL` (in seconds) environment variables.
+#[tokio::main]
+async fn main() -> Result<(), postnl::Error> {
+    let username = env::var("POSTNL_USERNAME").expect("POSTNL_USERNAME not set");
+    let password = env::var("POSTNL_PASSWORD").expect("POSTNL_PASSWORD not set");
+    let addr: SocketAddr = env::var("LISTEN")
+        .unwrap_or_else(|_| "127.0.0.1:8080".to_string())
+        .parse()
+        .expect("invalid LISTEN address");
+    let path = env::var("ICAL_PATH").unwrap_or_else(|_| "/deliveries.ics".to_string());
+    let ttl = env::var("CACHE_TTL")
+        .map(|ttl| ttl.parse().expect("invalid CACHE_TTL"))
+        .unwrap_or(15 * 60);
+
+    let client = PostNL::new()?.login(username, password).await?;
+
+    println!("Serving calendar on http://{}{}", addr, path);
+    IcalServer::new(client)
+        .path(path)
+        .ttl(Duration::from_secs(ttl))
+        .serve(addr)
+        .await
+}
@@ -0,0 +1,129 @@
+use crate::{Error, Result, Token};
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+
+/// The identity of the logged in account, as stored in the OpenID Connect id token
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct IdTokenClaims {
+    #[serde(rename = "sub")]
+    pub subject: String,
+    pub email: Option<String>,
+    pub name: Option<String>,
+    #[serde(rename = "iat", with = "ts_seconds")]
+    pub issued_at: DateTime<Utc>,
+    #[serde(rename = "exp", with = "ts_seconds")]
+    pub expires: DateTime<Utc>,
+}
+
+impl Token {
+    /// Decode the claims from the id token
+    ///
+    /// Note that the signature of the token is not verified.
+    pub fn claims(&self) -> Result<IdTokenClaims> {
+        let payload = self
+            .id_token
+            .expose_secret()
+            .split('.')
+            .nth(1)
+            .ok_or(Error::InvalidIdToken("Malformed token"))?;
+        let json = base64::decode_config(payload, base64::URL_SAFE_NO_PAD)
+            .map_err(|_| Error::InvalidIdToken("Invalid base64 in token payload"))?;
+
+        Ok(serde_json::from_slice(&json)?)
+    }
+}
+
+#[cfg(feature = "jwks")]
+pub use jwks::*;
+
+#[cfg(feature = "jwks")]
+mod jwks {
+    use super::IdTokenClaims;
+    use crate::{Error, Result, Token};
+    use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
+    use serde::Deserialize;
+
+    static JWKS_URL: &str = "https://jouw.postnl.nl/identity/.well-known/openid-configuration/jwks";
+    static ISSUER: &str = "https://jouw.postnl.nl/identity";
+    static CLIENT_ID: &str = "pwb-web";
+
+    /// The public keys used to sign the id tokens
+    #[derive(Clone, Debug, Deserialize)]
+    pub struct JwkSet {
+        keys: Vec<Jwk>,
+    }
+
+    #[derive(Clone, Debug, Deserialize)]
+    struct Jwk {
+        kid: Option<String>,
+        kty: String,
+        n: Option<String>,
+        e: Option<String>,
+    }
+
+    impl JwkSet {
+        /// Get the current signing keys from PostNL
+        pub async fn fetch() -> Result<Self> {
+            Ok(reqwest::get(JWKS_URL).await?.json().await?)
+        }
+    }
+
+    impl Token {
+        /// Decode the claims from the id token after verifying its signature, issuer and audience
+        pub fn verified_claims(&self, jwks: &JwkSet) -> Result<IdTokenClaims> {
+            let token = self.id_token.expose_secret();
+            let header = decode_header(token)?;
+
+            let key = jwks
+                .keys
+                .iter()
+                .filter(|key| key.kty == "RSA")
+                .find(|key| header.kid.is_none() || key.kid == header.kid)
+                .ok_or(Error::InvalidIdToken("No matching signing key"))?;
+            let (n, e) = match (&key.n, &key.e) {
+                (Some(n), Some(e)) => (n, e),
+                _ => return Err(Error::InvalidIdToken("Incomplete signing key")),
+            };
+
+            let mut validation = Validation::new(Algorithm::RS256);
+            validation.set_audience(&[CLIENT_ID]);
+            validation.iss = Some(ISSUER.to_string());
+
+            Ok(decode(token, &DecodingKey::from_rsa_components(n, e), &validation)?.claims)
+        }
+    }
+}
+
+#[test]
+fn test_claims() {
+    use crate::auth::{AccessToken, RefreshToken};
+    use chrono::TimeZone;
+
+    let payload = base64::encode_config(
+        r#"{"sub":"1234","email":"user@example.com","name":"Jan Jansen","iat":1590000000,"exp":1590003600}"#,
+        base64::URL_SAFE_NO_PAD,
+    );
+    let token = Token {
+        access: AccessToken("access".to_string()),
+        id_token: RefreshToken(format!("eyJhbGciOiJSUzI1NiJ9.{}.signature", payload)),
+        expires: Utc::now(),
+    };
+
+    assert_eq!(
+        IdTokenClaims {
+            subject: "1234".to_string(),
+            email: Some("user@example.com".to_string()),
+            name: Some("Jan Jansen".to_string()),
+            issued_at: Utc.timestamp(1590000000, 0),
+            expires: Utc.timestamp(1590003600, 0),
+        },
+        token.claims().unwrap()
+    );
+
+    let token = Token {
+        id_token: RefreshToken("garbage".to_string()),
+        ..token
+    };
+    assert!(token.claims().is_err());
+}
@@ -1,10 +1,13 @@
+pub use crate::diff::{diff, PackageChange};
 pub use crate::dimensions::{Dimensions, Weight};
-pub use crate::formatted::FormattedStatus;
+pub use crate::eta::{Confidence, Estimate, Eta};
+pub use crate::formatted::{FormattedStatus, FormattedStatusParams, Locale, Segment};
+pub use crate::time::TimeWindow;
 use chrono::{DateTime, NaiveTime, Utc};
 use iso_country::Country;
 use parse_display::Display;
 use serde::export::TryFrom;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 #[derive(Clone, Debug, Deserialize)]
@@ -20,7 +23,7 @@ pub struct Package {
     pub reroute: Option<ReRoute>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Address {
     #[serde(default)]
@@ -34,7 +37,7 @@ pub struct Address {
     pub formatted: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Party {
     #[serde(rename = "type")]
@@ -72,7 +75,7 @@ pub struct Status {
     pub formatted: Option<FormattedStatus>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Enroute {
     #[serde(rename = "timeframe")]
@@ -82,7 +85,7 @@ pub struct Enroute {
     pub trip_information: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct TimeFrame {
     pub planned_date: Option<DateTime<Utc>>,
@@ -97,7 +100,7 @@ pub struct TimeFrame {
     pub deviation_in_minutes: u32,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DeliveryLocation {
     pub header: String,
@@ -114,7 +117,7 @@ pub struct DeliveryLocation {
     pub formatted: String,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Delivery {
     pub delivery_date: Option<DateTime<Utc>>,
@@ -123,21 +126,28 @@ pub struct Delivery {
     pub delivery_address: Option<Box<Address>>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+/// Signature or photo taken at delivery
+#[derive(Clone, Debug)]
+pub struct ProofOfDelivery {
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReturnEligibility {
     pub can_return_at_retail: bool,
     pub pending_return_at_retail: bool,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct StatusPhase {
     pub index: u8,
     pub message: String,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReRoute {
     pub available: bool,
@@ -146,14 +156,14 @@ pub struct ReRoute {
     pub unavailability: Option<ReRouteUnavailability>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ReRouteUnavailability {
     pub text: String,
     pub link: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExtraStatusInformation {
     data: ExtraStatusInformationData,
@@ -161,18 +171,18 @@ pub struct ExtraStatusInformation {
     information_type: ExtraStatusInformationType,
 }
 
-#[derive(Clone, Debug, Deserialize, Display)]
+#[derive(Clone, Debug, Deserialize, Serialize, Display)]
 #[serde(rename_all = "camelCase")]
 pub struct ExtraStatusInformationData {
     text: String,
 }
 
-#[derive(Clone, Debug, Deserialize, Display, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Display, Eq, PartialEq)]
 pub enum ExtraStatusInformationType {
     Unknown,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Settings {
     pub title: String,
@@ -181,21 +191,21 @@ pub struct Settings {
     pub push_notification: PushStatus,
 }
 
-#[derive(Clone, Debug, Deserialize, Display, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Display, Eq, PartialEq)]
 pub enum ReRouteAvailability {
     AvailableAfterFirstAttempt,
     CustomerRelated,
     IncorrectStatus,
 }
 
-#[derive(Clone, Debug, Deserialize, Display, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Display, Eq, PartialEq)]
 pub enum PushStatus {
     Unavailable,
     On,
     Off,
 }
 
-#[derive(Clone, Debug, Deserialize, Display, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Display, Eq, PartialEq)]
 pub enum DeliveryStatus {
     Delivered,
     InTransit,
@@ -205,7 +215,7 @@ pub enum DeliveryStatus {
     EnrouteWholeDayOrUnspecified,
 }
 
-#[derive(Clone, Debug, Deserialize, Display, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Display, Eq, PartialEq)]
 pub enum TimeFrameType {
     Specific,
     Unspecified,
@@ -213,25 +223,25 @@ pub enum TimeFrameType {
     WholeDay,
 }
 
-#[derive(Clone, Debug, Deserialize, Display, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Display, Eq, PartialEq)]
 pub enum EnrouteType {
     Standard,
     Tentative,
 }
 
-#[derive(Clone, Debug, Deserialize, Display, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Display, Eq, PartialEq)]
 pub enum ShipmentType {
     LetterboxParcel,
     Parcel,
 }
 
-#[derive(Clone, Debug, Deserialize, Display, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Display, Eq, PartialEq)]
 pub enum BoxType {
     Receiver,
     Sender,
 }
 
-#[derive(Clone, Debug, Deserialize, Display, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Display, Eq, PartialEq)]
 pub enum PartyType {
     Recipient,
     Return,
@@ -239,7 +249,7 @@ pub enum PartyType {
     Rerouted,
 }
 
-#[derive(Clone, Debug, Deserialize, Display, Eq, PartialEq)]
+#[derive(Clone, Debug, Deserialize, Serialize, Display, Eq, PartialEq)]
 pub enum LocationType {
     Recipient,
     ServicePoint,
@@ -247,7 +257,7 @@ pub enum LocationType {
     PostOffice,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InboxPackage {
     pub shipment_type: ShipmentType,
@@ -282,7 +292,7 @@ pub struct InboxPackage {
     pub pickup_retail_barcode: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InboxProduct {
     pub product_code: String,
@@ -290,15 +300,20 @@ pub struct InboxProduct {
     pub product_characteristic: String,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InboxDelivery {
     pub barcode: String,
     pub status: DeliveryStatus,
     pub first_delivery_attempt_expired: bool,
+    /// Only present for delivered packages
+    #[serde(default)]
+    pub has_proof_of_delivery: bool,
+    #[serde(default)]
+    pub signature_url: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InboxParty {
     pub address_type: PartyType,
@@ -316,7 +331,7 @@ pub struct InboxParty {
     pub country: Country,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct InboxDeliveryLocation {
     pub location_type: LocationType,
@@ -329,20 +344,21 @@ pub struct InboxDeliveryLocation {
     pub list_name: String,
     pub coordinate: Coordinate,
     pub business_hours: Vec<OpeningHours>,
+    /// Distance from the address of the recipient in meters
     pub distance: u32,
     pub services: Vec<String>,
     pub delivery_date: Option<DateTime<Utc>>,
 }
 
 /// Note that these seem to be reversed for received packages
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InboxGeneratedTiles {
     pub receiver: String,
     pub sender: String,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InboxTrackedShipment {
     pub id: u32,
@@ -359,14 +375,14 @@ pub struct InboxTrackedShipment {
     pub key: String,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct InboxObservation {
-    observation_date: DateTime<Utc>,
-    observation_code: String,
+    pub observation_date: DateTime<Utc>,
+    pub observation_code: String,
 }
 
-#[derive(Clone, Debug, Deserialize, PartialEq, Display)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Display)]
 #[display("{height} x {width} x {depth}m")]
 pub struct InboxDimensions {
     pub height: f32,
@@ -375,13 +391,30 @@ pub struct InboxDimensions {
     pub volume: f32,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct Coordinate {
-    latitude: f32,
-    longitude: f32,
+    pub(crate) latitude: f32,
+    pub(crate) longitude: f32,
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+impl Coordinate {
+    pub fn new(latitude: f32, longitude: f32) -> Self {
+        Coordinate {
+            latitude,
+            longitude,
+        }
+    }
+
+    pub fn latitude(&self) -> f32 {
+        self.latitude
+    }
+
+    pub fn longitude(&self) -> f32 {
+        self.longitude
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, Eq, PartialEq)]
 pub enum Day {
     Monday,
     Tuesday,
@@ -392,14 +425,14 @@ pub enum Day {
     Sunday,
 }
 
-#[derive(Clone, Debug, Deserialize)]
-#[serde(try_from = "RawHours")]
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(try_from = "RawHours", into = "RawHours")]
 pub struct Hours {
-    from: NaiveTime,
-    to: NaiveTime,
+    pub(crate) from: NaiveTime,
+    pub(crate) to: NaiveTime,
 }
 
-#[derive(Clone, Debug, Deserialize)]
+#[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct RawHours {
     from: String,
     to: String,
@@ -416,8 +449,22 @@ impl TryFrom<RawHours> for Hours {
     }
 }
 
-#[derive(Clone, Debug, Deserialize)]
+impl From<Hours> for RawHours {
+    fn from(value: Hours) -> Self {
+        RawHours {
+            from: value.from.format("%H:%M").to_string(),
+            to: value.to.format("%H:%M").to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
 pub struct OpeningHours {
-    day: Day,
-    hours: Vec<Hours>,
+    pub(crate) day: Day,
+    pub(crate) hours: Vec<Hours>,
+}
+
+#[cfg(test)]
+pub(crate) fn fixture_package() -> InboxPackage {
+    serde_json::from_str(include_str!("fixtures/inbox_package.json")).unwrap()
 }
@@ -0,0 +1,198 @@
+use crate::data::{
+    DeliveryStatus, InboxDeliveryLocation, InboxObservation, InboxPackage, TimeFrame,
+};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// A single change between two snapshots of the inbox
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum PackageChange {
+    Added {
+        key: String,
+    },
+    Removed {
+        key: String,
+    },
+    DeliveryStatus {
+        key: String,
+        old: DeliveryStatus,
+        new: DeliveryStatus,
+    },
+    TimeFrame {
+        key: String,
+        old: Option<TimeFrame>,
+        new: Option<TimeFrame>,
+    },
+    DeliveryLocation {
+        key: String,
+        old: Option<InboxDeliveryLocation>,
+        new: Option<InboxDeliveryLocation>,
+    },
+    Observation {
+        key: String,
+        observation: InboxObservation,
+    },
+    FirstDeliveryAttemptFailed {
+        key: String,
+        failed: bool,
+    },
+}
+
+impl PackageChange {
+    /// The key of the package this change applies to
+    pub fn key(&self) -> &str {
+        match self {
+            PackageChange::Added { key }
+            | PackageChange::Removed { key }
+            | PackageChange::DeliveryStatus { key, .. }
+            | PackageChange::TimeFrame { key, .. }
+            | PackageChange::DeliveryLocation { key, .. }
+            | PackageChange::Observation { key, .. }
+            | PackageChange::FirstDeliveryAttemptFailed { key, .. } => key,
+        }
+    }
+}
+
+/// Compare two snapshots of the inbox
+///
+/// Changes are reported in the order of the packages in `new`, followed by removed packages in the order of `old`.
+pub fn diff(old: &[InboxPackage], new: &[InboxPackage]) -> Vec<PackageChange> {
+    let old_by_key: HashMap<&str, &InboxPackage> = old
+        .iter()
+        .map(|package| (package.key.as_str(), package))
+        .collect();
+    let mut changes = Vec::new();
+
+    for package in new {
+        match old_by_key.get(package.key.as_str()) {
+            Some(old_package) => diff_package(old_package, package, &mut changes),
+            None => changes.push(PackageChange::Added {
+                key: package.key.clone(),
+            }),
+        }
+    }
+
+    for package in old {
+        if !new.iter().any(|new_package| new_package.key == package.key) {
+            changes.push(PackageChange::Removed {
+                key: package.key.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+fn diff_package(old: &InboxPackage, new: &InboxPackage, changes: &mut Vec<PackageChange>) {
+    let key = &new.key;
+
+    if old.delivery.status != new.delivery.status {
+        changes.push(PackageChange::DeliveryStatus {
+            key: key.clone(),
+            old: old.delivery.status.clone(),
+            new: new.delivery.status.clone(),
+        });
+    }
+
+    let old_time_frame = old.enroute.as_ref().map(|enroute| &enroute.time_frame);
+    let new_time_frame = new.enroute.as_ref().map(|enroute| &enroute.time_frame);
+    if old_time_frame != new_time_frame {
+        changes.push(PackageChange::TimeFrame {
+            key: key.clone(),
+            old: old_time_frame.cloned(),
+            new: new_time_frame.cloned(),
+        });
+    }
+
+    if old.delivery_location != new.delivery_location {
+        changes.push(PackageChange::DeliveryLocation {
+            key: key.clone(),
+            old: old.delivery_location.clone(),
+            new: new.delivery_location.clone(),
+        });
+    }
+
+    for observation in &new.all_observations {
+        if !old.all_observations.contains(observation) {
+            changes.push(PackageChange::Observation {
+                key: key.clone(),
+                observation: observation.clone(),
+            });
+        }
+    }
+
+    if old.first_delivery_attempt_failed != new.first_delivery_attempt_failed {
+        changes.push(PackageChange::FirstDeliveryAttemptFailed {
+            key: key.clone(),
+            failed: new.first_delivery_attempt_failed,
+        });
+    }
+}
+
+#[test]
+fn test_diff_unchanged() {
+    let package = crate::data::fixture_package();
+
+    assert_eq!(
+        Vec::<PackageChange>::new(),
+        diff(&[package.clone()], &[package])
+    );
+}
+
+#[test]
+fn test_diff_added_removed() {
+    let old = crate::data::fixture_package();
+    let mut new = crate::data::fixture_package();
+    new.key = "other".to_string();
+
+    assert_eq!(
+        vec![
+            PackageChange::Added {
+                key: "other".to_string()
+            },
+            PackageChange::Removed {
+                key: old.key.clone()
+            },
+        ],
+        diff(&[old], &[new])
+    );
+}
+
+#[test]
+fn test_diff_fields() {
+    use chrono::{TimeZone, Utc};
+
+    let old = crate::data::fixture_package();
+    let mut new = crate::data::fixture_package();
+    new.delivery.status = DeliveryStatus::Delivered;
+    new.enroute = None;
+    new.first_delivery_attempt_failed = true;
+    let observation = InboxObservation {
+        observation_date: Utc.ymd(2020, 5, 21).and_hms(13, 0, 0),
+        observation_code: "I01".to_string(),
+    };
+    new.all_observations.push(observation.clone());
+
+    let key = old.key.clone();
+    assert_eq!(
+        vec![
+            PackageChange::DeliveryStatus {
+                key: key.clone(),
+                old: DeliveryStatus::EnrouteSpecific,
+                new: DeliveryStatus::Delivered,
+            },
+            PackageChange::TimeFrame {
+                key: key.clone(),
+                old: old.enroute.clone().map(|enroute| enroute.time_frame),
+                new: None,
+            },
+            PackageChange::Observation {
+                key: key.clone(),
+                observation,
+            },
+            PackageChange::FirstDeliveryAttemptFailed { key, failed: true },
+        ],
+        diff(&[old], &[new])
+    );
+}
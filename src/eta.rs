@@ -0,0 +1,312 @@
+use crate::data::{DeliveryStatus, EnrouteType, InboxPackage};
+use crate::time::{to_local, TimeWindow};
+use chrono::{DateTime, Duration, NaiveDate};
+use chrono_tz::Tz;
+
+/// The expected delivery of a package, in Dutch local time
+#[derive(Clone, Debug, PartialEq)]
+pub enum Eta {
+    /// The package has been delivered, with the time of delivery if known
+    Delivered {
+        at: Option<DateTime<Tz>>,
+    },
+    /// Delivery is expected within a time window, the end can be unknown
+    Window {
+        from: DateTime<Tz>,
+        to: Option<DateTime<Tz>>,
+    },
+    /// Delivery is expected at an unknown time during the day
+    Day(NaiveDate),
+    Unknown,
+}
+
+/// How reliable an estimate is
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum Confidence {
+    /// Tentative routes, deliveries after a failed attempt or no time frame at all
+    Low,
+    /// Planned times, time frames with a known deviation, only a delivery date or a delivery
+    /// time inferred from the latest observation
+    Medium,
+    /// Delivered packages or a specific time frame based on the actual route
+    High,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Estimate {
+    pub eta: Eta,
+    pub confidence: Confidence,
+}
+
+impl InboxPackage {
+    /// Estimate when the package will be delivered
+    pub fn eta(&self) -> Estimate {
+        match self.delivery.status {
+            DeliveryStatus::Delivered | DeliveryStatus::DeliveredAtPickup => {
+                let (at, confidence) = self.delivered_at();
+                return Estimate {
+                    eta: Eta::Delivered { at },
+                    confidence,
+                };
+            }
+            _ => {}
+        }
+
+        let enroute = match &self.enroute {
+            Some(enroute) => enroute,
+            None => {
+                // a known delivery date counts the same as a whole day time frame
+                return self
+                    .delivery_location
+                    .as_ref()
+                    .and_then(|location| location.delivery_date)
+                    .map(|date| Estimate {
+                        eta: Eta::Day(to_local(&date).naive_local().date()),
+                        confidence: if self.after_delivery_attempt() {
+                            Confidence::Low
+                        } else {
+                            Confidence::Medium
+                        },
+                    })
+                    .unwrap_or(Estimate {
+                        eta: Eta::Unknown,
+                        confidence: Confidence::Low,
+                    });
+            }
+        };
+        let time_frame = &enroute.time_frame;
+        // the deviation can go either way, so it widens the window on both sides
+        let deviation = Duration::minutes(i64::from(time_frame.deviation_in_minutes));
+
+        let (eta, confidence) = match time_frame.window() {
+            Some(TimeWindow::Between { from, to }) => {
+                let actual = time_frame.from.is_some() && time_frame.to.is_some();
+                let confidence = if actual && time_frame.deviation_in_minutes == 0 {
+                    Confidence::High
+                } else {
+                    Confidence::Medium
+                };
+                (
+                    Eta::Window {
+                        from: from - deviation,
+                        to: Some(to + deviation),
+                    },
+                    confidence,
+                )
+            }
+            Some(TimeWindow::From(from)) => (
+                Eta::Window {
+                    from: from - deviation,
+                    to: None,
+                },
+                Confidence::Medium,
+            ),
+            Some(TimeWindow::Day(date)) => (Eta::Day(date), Confidence::Medium),
+            None => (Eta::Unknown, Confidence::Low),
+        };
+
+        let unreliable =
+            enroute.enroute_type == EnrouteType::Tentative || self.after_delivery_attempt();
+
+        Estimate {
+            eta,
+            confidence: if unreliable {
+                Confidence::Low
+            } else {
+                confidence
+            },
+        }
+    }
+
+    fn after_delivery_attempt(&self) -> bool {
+        self.first_delivery_attempt_failed || !self.before_first_delivery_attempt
+    }
+
+    /// The date the package arrived at the pickup point, or the latest observation
+    ///
+    /// A time taken from the observations is only an approximation of the actual delivery.
+    fn delivered_at(&self) -> (Option<DateTime<Tz>>, Confidence) {
+        let delivery_date = self
+            .delivery_location
+            .as_ref()
+            .and_then(|location| location.delivery_date);
+        if let Some(date) = delivery_date {
+            return (Some(to_local(&date)), Confidence::High);
+        }
+
+        match self
+            .all_observations
+            .iter()
+            .map(|observation| observation.observation_date)
+            .max()
+        {
+            Some(date) => (Some(to_local(&date)), Confidence::Medium),
+            None => (None, Confidence::High),
+        }
+    }
+}
+
+#[cfg(test)]
+fn local(day: u32, hour: u32, minute: u32) -> DateTime<Tz> {
+    use chrono::TimeZone;
+
+    crate::time::TIMEZONE
+        .ymd(2020, 5, day)
+        .and_hms(hour, minute, 0)
+}
+
+#[test]
+fn test_eta_specific() {
+    let package = crate::data::fixture_package();
+    assert_eq!(
+        Estimate {
+            eta: Eta::Window {
+                from: local(21, 14, 30),
+                to: Some(local(21, 16, 30)),
+            },
+            confidence: Confidence::High,
+        },
+        package.eta()
+    );
+
+    let mut planned = crate::data::fixture_package();
+    let time_frame = &mut planned.enroute.as_mut().unwrap().time_frame;
+    time_frame.from = None;
+    time_frame.to = None;
+    assert_eq!(
+        Estimate {
+            eta: Eta::Window {
+                from: local(21, 14, 0),
+                to: Some(local(21, 16, 0)),
+            },
+            confidence: Confidence::Medium,
+        },
+        planned.eta()
+    );
+
+    let mut deviation = crate::data::fixture_package();
+    deviation
+        .enroute
+        .as_mut()
+        .unwrap()
+        .time_frame
+        .deviation_in_minutes = 15;
+    assert_eq!(
+        Estimate {
+            eta: Eta::Window {
+                from: local(21, 14, 15),
+                to: Some(local(21, 16, 45)),
+            },
+            confidence: Confidence::Medium,
+        },
+        deviation.eta()
+    );
+}
+
+#[test]
+fn test_eta_unreliable() {
+    use crate::data::TimeFrameType;
+
+    let mut tentative = crate::data::fixture_package();
+    tentative.enroute.as_mut().unwrap().enroute_type = EnrouteType::Tentative;
+    assert_eq!(Confidence::Low, tentative.eta().confidence);
+
+    let mut failed = crate::data::fixture_package();
+    failed.before_first_delivery_attempt = false;
+    failed.first_delivery_attempt_failed = true;
+    failed.enroute.as_mut().unwrap().time_frame.time_frame_type = TimeFrameType::WholeDay;
+    assert_eq!(
+        Estimate {
+            eta: Eta::Day(NaiveDate::from_ymd(2020, 5, 21)),
+            confidence: Confidence::Low,
+        },
+        failed.eta()
+    );
+}
+
+#[test]
+fn test_eta_without_time_frame() {
+    use crate::data::TimeFrameType;
+
+    let mut in_transit = crate::data::fixture_package();
+    in_transit.delivery.status = DeliveryStatus::InTransit;
+    in_transit.enroute = None;
+    in_transit.delivery_location = None;
+    assert_eq!(
+        Estimate {
+            eta: Eta::Unknown,
+            confidence: Confidence::Low,
+        },
+        in_transit.eta()
+    );
+
+    let mut delivery_date = in_transit.clone();
+    delivery_date.delivery_location = crate::data::fixture_package().delivery_location;
+    delivery_date
+        .delivery_location
+        .as_mut()
+        .unwrap()
+        .delivery_date = Some("2020-05-21T22:30:00Z".parse().unwrap());
+    assert_eq!(
+        Estimate {
+            eta: Eta::Day(NaiveDate::from_ymd(2020, 5, 22)),
+            confidence: Confidence::Medium,
+        },
+        delivery_date.eta()
+    );
+
+    let mut whole_day = crate::data::fixture_package();
+    whole_day
+        .enroute
+        .as_mut()
+        .unwrap()
+        .time_frame
+        .time_frame_type = TimeFrameType::WholeDay;
+    assert_eq!(
+        Estimate {
+            eta: Eta::Day(NaiveDate::from_ymd(2020, 5, 21)),
+            confidence: Confidence::Medium,
+        },
+        whole_day.eta()
+    );
+}
+
+#[test]
+fn test_eta_delivered() {
+    let mut delivered = crate::data::fixture_package();
+    delivered.delivery.status = DeliveryStatus::Delivered;
+    delivered.enroute = None;
+    assert_eq!(
+        Estimate {
+            eta: Eta::Delivered {
+                at: Some(local(21, 8, 40)),
+            },
+            confidence: Confidence::Medium,
+        },
+        delivered.eta()
+    );
+
+    let mut pickup = crate::data::fixture_package();
+    pickup.delivery.status = DeliveryStatus::DeliveredAtPickup;
+    pickup.delivery_location.as_mut().unwrap().delivery_date =
+        Some("2020-05-22T09:15:00Z".parse().unwrap());
+    assert_eq!(
+        Estimate {
+            eta: Eta::Delivered {
+                at: Some(local(22, 11, 15)),
+            },
+            confidence: Confidence::High,
+        },
+        pickup.eta()
+    );
+
+    let mut unknown = delivered.clone();
+    unknown.all_observations.clear();
+    assert_eq!(
+        Estimate {
+            eta: Eta::Delivered { at: None },
+            confidence: Confidence::High,
+        },
+        unknown.eta()
+    );
+}
@@ -1,4 +1,4 @@
-use chrono::{Date, DateTime, FixedOffset, NaiveTime};
+use chrono::{Date, DateTime, Datelike, FixedOffset, NaiveTime, Timelike};
 use once_cell::sync::Lazy;
 use regex::{Captures, Regex};
 use serde::export::TryFrom;
@@ -23,14 +23,22 @@ pub struct FormattedStatus {
     short_params: Vec<FormattedStatusParams>,
 }
 
-#[derive(Clone, Debug)]
-enum FormattedStatusParams {
+/// A date or time placeholder in a formatted status
+#[derive(Clone, Debug, PartialEq)]
+pub enum FormattedStatusParams {
     Date(Date<FixedOffset>),
     DateTime(DateTime<FixedOffset>),
     DateAbs(DateTime<FixedOffset>),
     Time(NaiveTime),
 }
 
+/// Part of a formatted status, either literal text or a date or time
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Segment<'a> {
+    Text(&'a str),
+    Param(&'a FormattedStatusParams),
+}
+
 impl fmt::Display for FormattedStatusParams {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -42,6 +50,99 @@ impl fmt::Display for FormattedStatusParams {
     }
 }
 
+/// Language used to render dates and times
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Locale {
+    Nl,
+    En,
+}
+
+impl Locale {
+    fn weekday(self, date: &impl Datelike) -> &'static str {
+        let index = date.weekday().num_days_from_monday() as usize;
+        match self {
+            Locale::Nl => [
+                "maandag",
+                "dinsdag",
+                "woensdag",
+                "donderdag",
+                "vrijdag",
+                "zaterdag",
+                "zondag",
+            ][index],
+            Locale::En => [
+                "Monday",
+                "Tuesday",
+                "Wednesday",
+                "Thursday",
+                "Friday",
+                "Saturday",
+                "Sunday",
+            ][index],
+        }
+    }
+
+    fn month(self, date: &impl Datelike) -> &'static str {
+        let index = date.month0() as usize;
+        match self {
+            Locale::Nl => [
+                "januari",
+                "februari",
+                "maart",
+                "april",
+                "mei",
+                "juni",
+                "juli",
+                "augustus",
+                "september",
+                "oktober",
+                "november",
+                "december",
+            ][index],
+            Locale::En => [
+                "January",
+                "February",
+                "March",
+                "April",
+                "May",
+                "June",
+                "July",
+                "August",
+                "September",
+                "October",
+                "November",
+                "December",
+            ][index],
+        }
+    }
+
+    fn date(self, date: &impl Datelike) -> String {
+        format!("{} {} {}", date.day(), self.month(date), date.year())
+    }
+
+    fn time(self, time: &impl Timelike) -> String {
+        format!("{:02}:{:02}", time.hour(), time.minute())
+    }
+}
+
+impl FormattedStatusParams {
+    pub(crate) fn render(&self, locale: Locale) -> String {
+        match self {
+            FormattedStatusParams::Date(date) => locale.date(date),
+            FormattedStatusParams::DateTime(date_time) => {
+                format!("{} {}", locale.date(date_time), locale.time(date_time))
+            }
+            FormattedStatusParams::DateAbs(date) => format!(
+                "{} {} {}",
+                locale.weekday(date),
+                date.day(),
+                locale.month(date)
+            ),
+            FormattedStatusParams::Time(time) => locale.time(time),
+        }
+    }
+}
+
 fn err_to_str(err: impl fmt::Display) -> String {
     format!("{}", err)
 }
@@ -84,6 +185,42 @@ impl FormattedStatus {
         Ok(params)
     }
 
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// The parts of the short status
+    pub fn short_segments(&self) -> impl Iterator<Item = Segment<'_>> {
+        Self::segments(&self.short_raw, &self.short_params)
+    }
+
+    /// The parts of the full status
+    pub fn body_segments(&self) -> impl Iterator<Item = Segment<'_>> {
+        Self::segments(&self.body_raw, &self.body_params)
+    }
+
+    fn segments<'a>(
+        format: &'a str,
+        params: &'a [FormattedStatusParams],
+    ) -> impl Iterator<Item = Segment<'a>> {
+        let mut params = params.iter();
+        let mut parts = format.split("{}").peekable();
+
+        std::iter::from_fn(move || {
+            let part = parts.next()?;
+            // every part except the last is followed by a placeholder
+            let param = parts.peek().map(|_| {
+                params
+                    .next()
+                    .map(Segment::Param)
+                    .unwrap_or(Segment::Text("{}"))
+            });
+            Some(std::iter::once(Segment::Text(part)).chain(param))
+        })
+        .flatten()
+        .filter(|segment| *segment != Segment::Text(""))
+    }
+
     pub fn short(&self) -> String {
         Self::format(&self.short_raw, &self.short_params)
     }
@@ -92,11 +229,27 @@ impl FormattedStatus {
         Self::format(&self.body_raw, &self.body_params)
     }
 
+    /// The short status with dates and times in the given language
+    pub fn short_in(&self, locale: Locale) -> String {
+        Self::format_in(&self.short_raw, &self.short_params, locale)
+    }
+
+    /// The full status with dates and times in the given language
+    pub fn body_in(&self, locale: Locale) -> String {
+        Self::format_in(&self.body_raw, &self.body_params, locale)
+    }
+
     fn format(format: &str, params: &[FormattedStatusParams]) -> String {
         params.iter().fold(format.to_string(), |result, param| {
             result.replacen("{}", &param.to_string(), 1)
         })
     }
+
+    fn format_in(format: &str, params: &[FormattedStatusParams], locale: Locale) -> String {
+        params.iter().fold(format.to_string(), |result, param| {
+            result.replacen("{}", &param.render(locale), 1)
+        })
+    }
 }
 
 static REPLACE_FORMATTED_PARAMS_REGEX: Lazy<Regex> =
@@ -138,3 +291,57 @@ fn test_formatting() {
     assert_eq!(formatted.short(), "Bezorgd op Tuesday 27 August");
     assert_eq!(formatted.body(), "Tuesday 27 August\n12:28:12 uur");
 }
+
+#[test]
+fn test_localized_formatting() {
+    use std::convert::TryInto;
+
+    let raw = RawFormattedStatus {
+        title: "Bezorgd op".to_string(),
+        body: "{DateAbs:2019-03-05T09:08:12+01:00}\n{time:2019-03-05T09:08:12+01:00} uur"
+            .to_string(),
+        short: "{date:2019-03-05T09:08:12+01:00}, {dateTime:2019-10-27T14:00:00+01:00}".to_string(),
+    };
+
+    let formatted: FormattedStatus = raw.try_into().unwrap();
+    assert_eq!(formatted.body_in(Locale::Nl), "dinsdag 5 maart\n09:08 uur");
+    assert_eq!(formatted.body_in(Locale::En), "Tuesday 5 March\n09:08 uur");
+    assert_eq!(
+        formatted.short_in(Locale::Nl),
+        "5 maart 2019, 27 oktober 2019 14:00"
+    );
+    assert_eq!(
+        formatted.short_in(Locale::En),
+        "5 March 2019, 27 October 2019 14:00"
+    );
+}
+
+#[test]
+fn test_segments() {
+    use chrono::TimeZone;
+    use std::convert::TryInto;
+
+    let raw = RawFormattedStatus {
+        title: "Bezorgd op".to_string(),
+        body: "{DateAbs:2019-08-27T12:28:12+02:00}\n{time:2019-08-27T12:28:12+02:00} uur"
+            .to_string(),
+        short: "Bezorgd".to_string(),
+    };
+
+    let formatted: FormattedStatus = raw.try_into().unwrap();
+    let date = FixedOffset::east(7200).ymd(2019, 8, 27).and_hms(12, 28, 12);
+    assert_eq!("Bezorgd op", formatted.title());
+    assert_eq!(
+        vec![
+            Segment::Param(&FormattedStatusParams::DateAbs(date)),
+            Segment::Text("\n"),
+            Segment::Param(&FormattedStatusParams::Time(date.time())),
+            Segment::Text(" uur"),
+        ],
+        formatted.body_segments().collect::<Vec<_>>()
+    );
+    assert_eq!(
+        vec![Segment::Text("Bezorgd")],
+        formatted.short_segments().collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,110 @@
+use crate::data::{Coordinate, InboxPackage};
+use serde_json::{json, Value};
+
+/// Mean radius of the earth in meters
+const EARTH_RADIUS: f64 = 6_371_000.0;
+
+impl Coordinate {
+    /// The great-circle distance to another coordinate in meters, using the haversine formula
+    pub fn distance(&self, other: &Coordinate) -> f64 {
+        let lat1 = f64::from(self.latitude).to_radians();
+        let lat2 = f64::from(other.latitude).to_radians();
+        let delta_lat = lat2 - lat1;
+        let delta_lon = (f64::from(other.longitude) - f64::from(self.longitude)).to_radians();
+
+        let a = (delta_lat / 2.0).sin().powi(2)
+            + lat1.cos() * lat2.cos() * (delta_lon / 2.0).sin().powi(2);
+        2.0 * EARTH_RADIUS * a.sqrt().asin()
+    }
+
+    /// GeoJSON point geometry for the coordinate
+    pub fn to_geojson(&self) -> Value {
+        json!({
+            "type": "Point",
+            "coordinates": [self.longitude, self.latitude],
+        })
+    }
+}
+
+/// Create a GeoJSON feature collection with all delivery locations of the packages
+///
+/// Packages delivered at the same location share a single feature.
+pub fn delivery_locations(packages: &[InboxPackage]) -> Value {
+    let mut features: Vec<(&str, Value)> = Vec::new();
+
+    for package in packages {
+        let location = match &package.delivery_location {
+            Some(location) => location,
+            None => continue,
+        };
+
+        match features
+            .iter_mut()
+            .find(|(location_id, _)| *location_id == location.location_id)
+        {
+            Some((_, feature)) => {
+                if let Some(barcodes) = feature["properties"]["packages"].as_array_mut() {
+                    barcodes.push(package.barcode.clone().into());
+                }
+            }
+            None => features.push((
+                &location.location_id,
+                json!({
+                    "type": "Feature",
+                    "geometry": location.coordinate.to_geojson(),
+                    "properties": {
+                        "locationId": location.location_id,
+                        "name": location.name,
+                        "type": location.location_type.to_string(),
+                        "address": location.address.formatted,
+                        "packages": [package.barcode],
+                    },
+                }),
+            )),
+        }
+    }
+
+    json!({
+        "type": "FeatureCollection",
+        "features": features.into_iter().map(|(_, feature)| feature).collect::<Vec<_>>(),
+    })
+}
+
+#[test]
+fn test_distance() {
+    let amsterdam = Coordinate::new(52.3731, 4.8922);
+    let utrecht = Coordinate::new(52.0907, 5.1214);
+
+    assert_eq!(0.0, amsterdam.distance(&amsterdam));
+    assert!((amsterdam.distance(&utrecht) - 34_900.0).abs() < 200.0);
+    assert_eq!(amsterdam.distance(&utrecht), utrecht.distance(&amsterdam));
+}
+
+#[test]
+fn test_delivery_locations() {
+    let package = crate::data::fixture_package();
+    let mut other = crate::data::fixture_package();
+    other.barcode = "3SOTHER".to_string();
+    let mut home = crate::data::fixture_package();
+    home.delivery_location = None;
+
+    let collection = delivery_locations(&[package, other, home]);
+    let coordinates = collection["features"][0]["geometry"]["coordinates"]
+        .as_array()
+        .unwrap();
+
+    assert_eq!("FeatureCollection", collection["type"]);
+    assert_eq!(1, collection["features"].as_array().unwrap().len());
+    assert!((coordinates[0].as_f64().unwrap() - 4.8922).abs() < 0.0001);
+    assert!((coordinates[1].as_f64().unwrap() - 52.3731).abs() < 0.0001);
+    assert_eq!(
+        json!({
+            "locationId": "123456",
+            "name": "Supermarkt Centrum",
+            "type": "ServicePoint",
+            "address": "Hoofdstraat 10, 1234AC Amsterdam",
+            "packages": ["3SABCD1234567", "3SOTHER"],
+        }),
+        collection["features"][0]["properties"]
+    );
+}
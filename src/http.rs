@@ -0,0 +1,26 @@
+use crate::Result;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+
+/// Serve http on `addr`, answering every request with `handler`
+pub(crate) async fn serve<H, F>(addr: SocketAddr, handler: H) -> Result<()>
+where
+    H: Fn(Request<Body>) -> F + Clone + Send + Sync + 'static,
+    F: Future<Output = Response<Body>> + Send + 'static,
+{
+    let make_service = make_service_fn(move |_| {
+        let handler = handler.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let response = handler(request);
+                async move { Ok::<_, Infallible>(response.await) }
+            }))
+        }
+    });
+
+    Server::try_bind(&addr)?.serve(make_service).await?;
+    Ok(())
+}
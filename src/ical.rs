@@ -0,0 +1,209 @@
+use crate::data::{InboxPackage, TimeFrame};
+use crate::time::TimeWindow;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+
+static DATE_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+static DATE_FORMAT: &str = "%Y%m%d";
+
+/// Create a calendar with an event for the expected delivery of every package that is en route
+pub fn calendar(packages: &[InboxPackage]) -> String {
+    render(packages, Utc::now())
+}
+
+fn render(packages: &[InboxPackage], stamp: DateTime<Utc>) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//postnl-rs//PostNL deliveries//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:PostNL deliveries".to_string(),
+    ];
+
+    for package in packages {
+        if let Some(event) = event(package, stamp) {
+            lines.extend(event);
+        }
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().fold(String::new(), |mut calendar, line| {
+        calendar.push_str(&fold(line));
+        calendar.push_str("\r\n");
+        calendar
+    })
+}
+
+fn event(package: &InboxPackage, stamp: DateTime<Utc>) -> Option<Vec<String>> {
+    let time_frame = &package.enroute.as_ref()?.time_frame;
+
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}@postnl-rs", escape(&package.key)),
+        format!("DTSTAMP:{}", stamp.format(DATE_TIME_FORMAT)),
+    ];
+    lines.extend(event_time(time_frame)?);
+    lines.push(format!(
+        "SUMMARY:{}",
+        escape(&package.generated_titles.receiver)
+    ));
+    lines.push(format!("DESCRIPTION:{}", escape(&package.barcode)));
+    if let Some(location) = &package.delivery_location {
+        let address = location
+            .address
+            .formatted
+            .as_ref()
+            .map(|address| format!("{}, {}", location.name, address))
+            .unwrap_or_else(|| location.name.clone());
+        lines.push(format!("LOCATION:{}", escape(&address)));
+    }
+    lines.push("TRANSP:TRANSPARENT".to_string());
+    lines.push("END:VEVENT".to_string());
+
+    Some(lines)
+}
+
+fn event_time(time_frame: &TimeFrame) -> Option<Vec<String>> {
+    match time_frame.window()? {
+        TimeWindow::Between { from, to } => Some(vec![
+            format!(
+                "DTSTART:{}",
+                from.with_timezone(&Utc).format(DATE_TIME_FORMAT)
+            ),
+            format!("DTEND:{}", to.with_timezone(&Utc).format(DATE_TIME_FORMAT)),
+        ]),
+        // without an end time, the event only marks the start of the delivery window
+        TimeWindow::From(from) => Some(vec![format!(
+            "DTSTART:{}",
+            from.with_timezone(&Utc).format(DATE_TIME_FORMAT)
+        )]),
+        TimeWindow::Day(date) => Some(whole_day(date)),
+    }
+}
+
+/// All day event on the local date
+fn whole_day(date: NaiveDate) -> Vec<String> {
+    vec![
+        format!("DTSTART;VALUE=DATE:{}", date.format(DATE_FORMAT)),
+        format!(
+            "DTEND;VALUE=DATE:{}",
+            (date + Duration::days(1)).format(DATE_FORMAT)
+        ),
+    ]
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold content lines longer than 75 octets, without splitting utf8 characters
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            line_length = 1;
+        }
+        line_length += c.len_utf8();
+        folded.push(c);
+    }
+    folded
+}
+
+#[test]
+fn test_calendar() {
+    use chrono::TimeZone;
+
+    let package = crate::data::fixture_package();
+    let mut delivered = package.clone();
+    delivered.enroute = None;
+
+    let calendar = render(&[package, delivered], Utc.ymd(2020, 5, 20).and_hms(9, 0, 0));
+    assert_eq!(
+        "BEGIN:VCALENDAR\r\n\
+         VERSION:2.0\r\n\
+         PRODID:-//postnl-rs//PostNL deliveries//EN\r\n\
+         CALSCALE:GREGORIAN\r\n\
+         X-WR-CALNAME:PostNL deliveries\r\n\
+         BEGIN:VEVENT\r\n\
+         UID:3SABCD1234567-NL-1234AB@postnl-rs\r\n\
+         DTSTAMP:20200520T090000Z\r\n\
+         DTSTART:20200521T123000Z\r\n\
+         DTEND:20200521T143000Z\r\n\
+         SUMMARY:Webshop B.V.\r\n\
+         DESCRIPTION:3SABCD1234567\r\n\
+         LOCATION:Supermarkt Centrum\\, Hoofdstraat 10\\, 1234AC Amsterdam\r\n\
+         TRANSP:TRANSPARENT\r\n\
+         END:VEVENT\r\n\
+         END:VCALENDAR\r\n",
+        calendar
+    );
+}
+
+#[test]
+fn test_event_time() {
+    use crate::data::TimeFrameType;
+
+    let mut time_frame = crate::data::fixture_package().enroute.unwrap().time_frame;
+
+    time_frame.time_frame_type = TimeFrameType::OnlyFromTime;
+    assert_eq!(
+        Some(vec!["DTSTART:20200521T123000Z".to_string()]),
+        event_time(&time_frame)
+    );
+
+    time_frame.time_frame_type = TimeFrameType::WholeDay;
+    assert_eq!(
+        Some(vec![
+            "DTSTART;VALUE=DATE:20200521".to_string(),
+            "DTEND;VALUE=DATE:20200522".to_string()
+        ]),
+        event_time(&time_frame)
+    );
+
+    time_frame.time_frame_type = TimeFrameType::Unspecified;
+    time_frame.date = None;
+    time_frame.planned_date = None;
+    time_frame.from = None;
+    time_frame.planned_from = None;
+    assert_eq!(None, event_time(&time_frame));
+}
+
+#[test]
+fn test_event_time_local_midnight() {
+    use crate::data::TimeFrameType;
+
+    // dates are sent as midnight in Dutch local time, which is the previous day in UTC
+    let mut time_frame: TimeFrame =
+        serde_json::from_str(include_str!("fixtures/whole_day_timeframe.json")).unwrap();
+    assert_eq!(
+        Some(vec![
+            "DTSTART;VALUE=DATE:20200521".to_string(),
+            "DTEND;VALUE=DATE:20200522".to_string()
+        ]),
+        event_time(&time_frame)
+    );
+
+    time_frame.time_frame_type = TimeFrameType::Unspecified;
+    time_frame.date = Some("2020-12-01T00:00:00+01:00".parse().unwrap());
+    assert_eq!(
+        Some(vec![
+            "DTSTART;VALUE=DATE:20201201".to_string(),
+            "DTEND;VALUE=DATE:20201202".to_string()
+        ]),
+        event_time(&time_frame)
+    );
+}
+
+#[test]
+fn test_fold() {
+    let line = format!("SUMMARY:{}", "é".repeat(40));
+    let folded = fold(&line);
+
+    assert!(folded.split("\r\n").all(|line| line.len() <= 75));
+    assert_eq!(line, folded.replace("\r\n ", ""));
+}
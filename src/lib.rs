@@ -1,17 +1,54 @@
-use crate::data::InboxPackage;
+use crate::data::{InboxDelivery, InboxPackage, ProofOfDelivery, PushStatus, Settings};
 use err_derive::Error;
 
-use crate::auth::{AccessToken, AuthHandler};
-use reqwest::header;
+use crate::auth::AuthHandler;
+use iso_country::Country;
+use reqwest::{header, Method, RequestBuilder, StatusCode};
 use std::sync::Mutex;
+use tracing::{debug, field::Empty, instrument, Span};
+use url::Url;
+use zeroize::Zeroizing;
 
-pub use crate::auth::{AuthState, LoggedIn, New, Token};
-use serde::Deserialize;
+pub use crate::auth::{AccessToken, AuthState, LoggedIn, New, RefreshToken, Token};
+pub use crate::claims::IdTokenClaims;
+#[cfg(feature = "jwks")]
+pub use crate::claims::JwkSet;
+pub use crate::locations::SearchLocation;
+pub use crate::multi::{AccountPackage, MultiAccount, MultiAccountPackages};
+pub use crate::reroute::{ReRouteChoice, ReRouteOption, ReRouteUnavailable};
+#[cfg(feature = "server")]
+pub use crate::server::IcalServer;
+use serde::{Deserialize, Serialize};
 
 mod auth;
+mod claims;
 pub mod data;
+mod diff;
 mod dimensions;
+mod eta;
 mod formatted;
+pub mod geo;
+#[cfg(any(feature = "server", feature = "metrics"))]
+mod http;
+pub mod ical;
+mod locations;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+mod multi;
+mod opening_hours;
+mod render;
+mod reroute;
+#[cfg(feature = "server")]
+mod server;
+#[cfg(feature = "store")]
+pub mod store;
+pub mod time;
+#[cfg(feature = "vault")]
+pub mod vault;
+#[cfg(feature = "webhook")]
+pub mod webhook;
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -35,6 +72,34 @@ pub enum Error {
     Authentication,
     #[error(display = "Connection blocked by PostNL, try again in a while")]
     Blocked,
+    #[error(display = "Push notifications are not available for this package")]
+    PushNotificationUnavailable,
+    #[error(display = "Package can't be rerouted: {}", _0)]
+    ReRouteUnavailable(crate::reroute::ReRouteUnavailable),
+    #[error(display = "Invalid id token: {}", _0)]
+    InvalidIdToken(&'static str),
+    #[error(display = "Invalid url: {}", _0)]
+    InvalidUrl(#[error(source)] url::ParseError),
+    #[cfg(feature = "jwks")]
+    #[error(display = "Failed to verify id token: {}", _0)]
+    IdTokenVerification(#[error(source)] jsonwebtoken::errors::Error),
+    #[error(display = "IO error: {}", _0)]
+    Io(#[error(source)] std::io::Error),
+    #[cfg(feature = "vault")]
+    #[error(display = "Failed to decrypt vault, wrong passphrase or corrupted data")]
+    VaultDecryption,
+    #[cfg(feature = "store")]
+    #[error(display = "Error while accessing the history store: {}", _0)]
+    Store(#[error(source)] rusqlite::Error),
+    #[cfg(any(feature = "server", feature = "metrics"))]
+    #[error(display = "Http server error: {}", _0)]
+    Server(#[error(source)] hyper::Error),
+    #[cfg(feature = "webhook")]
+    #[error(display = "Failed to deliver webhook event to {}", _0)]
+    WebhookDelivery(String),
+    #[cfg(feature = "mqtt")]
+    #[error(display = "Failed to publish mqtt message: {}", _0)]
+    Mqtt(#[error(source)] rumqttc::ClientError),
 }
 
 type Result<T> = std::result::Result<T, Error>;
@@ -45,7 +110,9 @@ pub struct PostNL<State: AuthState> {
     auth_handler: AuthHandler<State>,
 }
 
+static POSTNL_URL: &str = "https://jouw.postnl.nl/";
 static INBOX_URL: &str = "https://jouw.postnl.nl/web/api/default/inbox";
+static TRACKED_SHIPMENT_URL: &str = "https://jouw.postnl.nl/web/api/default/trackedshipment";
 
 // old? api endpoints
 static _SHIPMENTS_URL: &str = "https://jouw.postnl.nl/web/api/shipments";
@@ -73,10 +140,16 @@ impl PostNL<New> {
         })
     }
 
+    /// Log in to the PostNL account
+    ///
+    /// The password is moved into a `Zeroizing` wrapper that is cleared once the login request has been
+    /// built. Only that copy is zeroized: the request body held by reqwest, and any copy the caller
+    /// made to pass an owned `String`, are not.
+    #[instrument(skip(self, username, password))]
     pub async fn login(
         self,
         username: impl AsRef<str>,
-        password: impl AsRef<str>,
+        password: impl Into<Zeroizing<String>>,
     ) -> Result<PostNL<LoggedIn>> {
         let PostNL {
             token,
@@ -85,7 +158,7 @@ impl PostNL<New> {
         } = self;
 
         let auth_handler = auth_handler
-            .login(username.as_ref(), password.as_ref())
+            .login(username.as_ref(), password.into())
             .await?;
 
         Ok(PostNL {
@@ -103,7 +176,10 @@ impl PostNL<LoggedIn> {
 
         let new_token = match token {
             Some(old_token) if !old_token.need_refresh() => old_token,
-            _ => self.auth_handler.generate_token().await?,
+            _ => {
+                debug!("no valid token, generating new token");
+                self.auth_handler.generate_token().await?
+            }
         };
 
         let access_token = new_token.access.clone();
@@ -124,21 +200,213 @@ impl PostNL<LoggedIn> {
         self.token.lock().unwrap().replace(token);
     }
 
-    pub async fn get_packages(&self) -> Result<Vec<InboxPackage>> {
+    /// Build a request with the bearer token
+    async fn request(&self, method: Method, url: &str) -> Result<RequestBuilder> {
         let token = self.authenticate().await?;
-
         Ok(self
             .client
-            .get(INBOX_URL)
-            .bearer_auth(token)
+            .request(method, url)
+            .bearer_auth(token.expose_secret()))
+    }
+
+    #[instrument(skip(self), fields(status = Empty, count = Empty))]
+    pub async fn get_packages(&self) -> Result<Vec<InboxPackage>> {
+        let response = self.request(Method::GET, INBOX_URL).await?.send().await?;
+        Span::current().record("status", &response.status().as_u16());
+
+        let packages = response.json::<InboxResponse>().await?.receiver;
+        Span::current().record("count", &packages.len());
+
+        Ok(packages)
+    }
+
+    /// Add a shipment to the inbox of the account
+    #[instrument(skip(self, title), fields(status = Empty))]
+    pub async fn add_tracked_shipment(
+        &self,
+        barcode: &str,
+        postal_code: &str,
+        country: Country,
+        title: Option<&str>,
+    ) -> Result<()> {
+        let response = self
+            .request(Method::POST, TRACKED_SHIPMENT_URL)
+            .await?
+            .json(&TrackedShipmentRequest {
+                barcode,
+                postal_code,
+                country,
+                title,
+            })
             .send()
+            .await?;
+        Span::current().record("status", &response.status().as_u16());
+
+        response.error_for_status()?;
+        Ok(())
+    }
+
+    /// Remove a shipment from the inbox of the account
+    #[instrument(skip(self), fields(status = Empty))]
+    pub async fn remove_tracked_shipment(&self, key: &str) -> Result<()> {
+        let response = self
+            .request(Method::DELETE, &api_url(TRACKED_SHIPMENT_URL, &[key]))
             .await?
-            .json::<InboxResponse>()
+            .send()
+            .await?;
+        Span::current().record("status", &response.status().as_u16());
+
+        response.error_for_status()?;
+        Ok(())
+    }
+
+    /// Download the signature or photo taken at delivery
+    ///
+    /// Returns `None` without sending a request if no proof of delivery is available. The access
+    /// token is only sent along if the proof is hosted on jouw.postnl.nl.
+    #[instrument(skip(self, package), fields(key = %package.key, status = Empty))]
+    pub async fn get_proof_of_delivery(
+        &self,
+        package: &InboxPackage,
+    ) -> Result<Option<ProofOfDelivery>> {
+        let url = match proof_of_delivery_url(&package.delivery)? {
+            Some(url) => url,
+            None => return Ok(None),
+        };
+
+        let request = if is_postnl_url(&url) {
+            self.request(Method::GET, url.as_str()).await?
+        } else {
+            self.client.get(url)
+        };
+        let response = request.send().await?;
+        Span::current().record("status", &response.status().as_u16());
+
+        let response = response.error_for_status()?;
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or("application/octet-stream")
+            .to_string();
+
+        Ok(Some(ProofOfDelivery {
+            content_type,
+            data: response.bytes().await?.to_vec(),
+        }))
+    }
+
+    /// Set a custom title for a package
+    pub async fn set_package_title(&self, key: &str, title: &str) -> Result<()> {
+        self.update_settings(
+            key,
+            &SettingsUpdate {
+                title: Some(title),
+                ..SettingsUpdate::default()
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Enable or disable push notifications for a package, returns the updated status
+    ///
+    /// Fails with `PushNotificationUnavailable` if the package doesn't support push notifications.
+    pub async fn set_push_notification(&self, key: &str, enabled: bool) -> Result<PushStatus> {
+        let settings = self
+            .update_settings(
+                key,
+                &SettingsUpdate {
+                    push_notification: Some(if enabled {
+                        PushStatus::On
+                    } else {
+                        PushStatus::Off
+                    }),
+                    ..SettingsUpdate::default()
+                },
+            )
+            .await
+            .map_err(|err| match &err {
+                Error::NetworkError(source) if push_notification_rejected(source.status()) => {
+                    Error::PushNotificationUnavailable
+                }
+                _ => err,
+            })?;
+
+        push_status(settings.push_notification)
+    }
+
+    /// Like `set_push_notification`, but fails without sending a request if the `current` status of
+    /// the package is already known to be `Unavailable`
+    pub async fn set_push_notification_with_status(
+        &self,
+        key: &str,
+        current: PushStatus,
+        enabled: bool,
+    ) -> Result<PushStatus> {
+        push_status(current)?;
+        self.set_push_notification(key, enabled).await
+    }
+
+    #[instrument(skip(self, update), fields(status = Empty))]
+    async fn update_settings(&self, key: &str, update: &SettingsUpdate<'_>) -> Result<Settings> {
+        let response = self
+            .request(
+                Method::PUT,
+                &api_url(TRACKED_SHIPMENT_URL, &[key, "settings"]),
+            )
             .await?
-            .receiver)
+            .json(update)
+            .send()
+            .await?;
+        Span::current().record("status", &response.status().as_u16());
+
+        Ok(response.error_for_status()?.json().await?)
     }
 }
 
+/// The signature url of a delivery, resolved against jouw.postnl.nl
+fn proof_of_delivery_url(delivery: &InboxDelivery) -> Result<Option<Url>> {
+    match &delivery.signature_url {
+        Some(url) if delivery.has_proof_of_delivery => Ok(Some(
+            Url::parse(POSTNL_URL).expect("invalid api url").join(url)?,
+        )),
+        _ => Ok(None),
+    }
+}
+
+/// Whether the url can be trusted with the access token
+fn is_postnl_url(url: &Url) -> bool {
+    url.scheme() == "https" && url.host_str() == Some("jouw.postnl.nl")
+}
+
+fn push_status(status: PushStatus) -> Result<PushStatus> {
+    match status {
+        PushStatus::Unavailable => Err(Error::PushNotificationUnavailable),
+        status => Ok(status),
+    }
+}
+
+/// The statuses the api responds with when the push notification setting can't be changed
+fn push_notification_rejected(status: Option<StatusCode>) -> bool {
+    matches!(
+        status,
+        Some(StatusCode::BAD_REQUEST)
+            | Some(StatusCode::FORBIDDEN)
+            | Some(StatusCode::CONFLICT)
+            | Some(StatusCode::UNPROCESSABLE_ENTITY)
+    )
+}
+
+/// Append path segments to an api url, percent encoding them
+fn api_url(base: &str, segments: &[&str]) -> String {
+    let mut url = Url::parse(base).expect("invalid api url");
+    url.path_segments_mut()
+        .expect("api url can't be a base")
+        .extend(segments);
+    url.to_string()
+}
+
 #[derive(Deserialize)]
 struct InboxResponse {
     // last_synchronization_date: DateTime<Utc>,
@@ -146,3 +414,143 @@ struct InboxResponse {
     // sender: Vec<InboxPackage>,
     // orders: Vec<InboxPackage>,
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct TrackedShipmentRequest<'a> {
+    barcode: &'a str,
+    postal_code: &'a str,
+    country: Country,
+    title: Option<&'a str>,
+}
+
+#[derive(Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SettingsUpdate<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    push_notification: Option<PushStatus>,
+}
+
+#[test]
+fn test_api_url() {
+    assert_eq!(
+        "https://jouw.postnl.nl/web/api/default/trackedshipment/3SABCD1234567-NL-1234AB/settings",
+        api_url(
+            TRACKED_SHIPMENT_URL,
+            &["3SABCD1234567-NL-1234AB", "settings"]
+        )
+    );
+    assert_eq!(
+        "https://jouw.postnl.nl/web/api/default/trackedshipment/a%20b%2Fc%3Fd",
+        api_url(TRACKED_SHIPMENT_URL, &["a b/c?d"])
+    );
+}
+
+#[test]
+fn test_tracked_shipment_request() {
+    use serde_json::json;
+
+    let request = TrackedShipmentRequest {
+        barcode: "3SABCD1234567",
+        postal_code: "1234AB",
+        country: Country::NL,
+        title: None,
+    };
+    assert_eq!(
+        json!({
+            "barcode": "3SABCD1234567",
+            "postalCode": "1234AB",
+            "country": "NL",
+            "title": null,
+        }),
+        serde_json::to_value(&request).unwrap()
+    );
+}
+
+#[test]
+fn test_settings_update() {
+    use serde_json::json;
+
+    let title = SettingsUpdate {
+        title: Some("Boeken"),
+        ..SettingsUpdate::default()
+    };
+    assert_eq!(
+        json!({ "title": "Boeken" }),
+        serde_json::to_value(&title).unwrap()
+    );
+
+    let push_notification = SettingsUpdate {
+        push_notification: Some(PushStatus::Off),
+        ..SettingsUpdate::default()
+    };
+    assert_eq!(
+        json!({ "pushNotification": "Off" }),
+        serde_json::to_value(&push_notification).unwrap()
+    );
+}
+
+#[test]
+fn test_push_status() {
+    assert_eq!(PushStatus::On, push_status(PushStatus::On).unwrap());
+    assert_eq!(PushStatus::Off, push_status(PushStatus::Off).unwrap());
+    assert!(matches!(
+        push_status(PushStatus::Unavailable),
+        Err(Error::PushNotificationUnavailable)
+    ));
+
+    assert!(push_notification_rejected(Some(StatusCode::BAD_REQUEST)));
+    assert!(!push_notification_rejected(Some(StatusCode::UNAUTHORIZED)));
+    assert!(!push_notification_rejected(None));
+}
+
+#[test]
+fn test_proof_of_delivery_url() {
+    let mut delivery = crate::data::fixture_package().delivery;
+    delivery.signature_url = Some("https://jouw.postnl.nl/signature/3SABCD1234567".to_string());
+    assert_eq!(None, proof_of_delivery_url(&delivery).unwrap());
+
+    delivery.has_proof_of_delivery = true;
+    let url = proof_of_delivery_url(&delivery).unwrap().unwrap();
+    assert_eq!(
+        "https://jouw.postnl.nl/signature/3SABCD1234567",
+        url.as_str()
+    );
+    assert!(is_postnl_url(&url));
+
+    delivery.signature_url = None;
+    assert_eq!(None, proof_of_delivery_url(&delivery).unwrap());
+}
+
+#[test]
+fn test_proof_of_delivery_url_relative() {
+    let mut delivery = crate::data::fixture_package().delivery;
+    delivery.has_proof_of_delivery = true;
+    delivery.signature_url = Some("/web/api/default/signature/3SABCD1234567".to_string());
+
+    let url = proof_of_delivery_url(&delivery).unwrap().unwrap();
+    assert_eq!(
+        "https://jouw.postnl.nl/web/api/default/signature/3SABCD1234567",
+        url.as_str()
+    );
+    assert!(is_postnl_url(&url));
+}
+
+#[test]
+fn test_proof_of_delivery_url_foreign_host() {
+    let mut delivery = crate::data::fixture_package().delivery;
+    delivery.has_proof_of_delivery = true;
+
+    for url in &[
+        "https://cdn.example.com/signature/3SABCD1234567.png",
+        "https://jouw.postnl.nl.example.com/signature.png",
+        "http://jouw.postnl.nl/signature.png",
+        "//cdn.example.com/signature.png",
+    ] {
+        delivery.signature_url = Some(url.to_string());
+        let url = proof_of_delivery_url(&delivery).unwrap().unwrap();
+        assert!(!is_postnl_url(&url), "{}", url);
+    }
+}
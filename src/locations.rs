@@ -0,0 +1,87 @@
+use crate::data::{Coordinate, InboxDeliveryLocation};
+use crate::{LoggedIn, PostNL, Result};
+use reqwest::Method;
+use serde::Deserialize;
+use tracing::{field::Empty, instrument, Span};
+
+static LOCATIONS_URL: &str = "https://jouw.postnl.nl/web/api/default/locations";
+
+/// The location to search pickup points around
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchLocation {
+    PostalCode(String),
+    Coordinate(Coordinate),
+}
+
+impl From<Coordinate> for SearchLocation {
+    fn from(coordinate: Coordinate) -> Self {
+        SearchLocation::Coordinate(coordinate)
+    }
+}
+
+impl SearchLocation {
+    fn query(&self, radius: u32) -> Vec<(&'static str, String)> {
+        let mut query = match self {
+            SearchLocation::PostalCode(postal_code) => {
+                vec![("postalCode", postal_code.replace(' ', "").to_uppercase())]
+            }
+            SearchLocation::Coordinate(coordinate) => vec![
+                ("latitude", coordinate.latitude.to_string()),
+                ("longitude", coordinate.longitude.to_string()),
+            ],
+        };
+        query.push(("radius", radius.to_string()));
+        query
+    }
+}
+
+#[derive(Deserialize)]
+struct LocationsResponse {
+    locations: Vec<InboxDeliveryLocation>,
+}
+
+impl PostNL<LoggedIn> {
+    /// Find the pickup points within `radius` meters of a location
+    #[instrument(skip(self), fields(status = Empty, count = Empty))]
+    pub async fn find_pickup_points(
+        &self,
+        location: SearchLocation,
+        radius: u32,
+    ) -> Result<Vec<InboxDeliveryLocation>> {
+        let response = self
+            .request(Method::GET, LOCATIONS_URL)
+            .await?
+            .query(&location.query(radius))
+            .send()
+            .await?;
+        Span::current().record("status", &response.status().as_u16());
+
+        let locations = response
+            .error_for_status()?
+            .json::<LocationsResponse>()
+            .await?
+            .locations;
+        Span::current().record("count", &locations.len());
+
+        Ok(locations)
+    }
+}
+
+#[test]
+fn test_query() {
+    assert_eq!(
+        vec![
+            ("postalCode", "1234AB".to_string()),
+            ("radius", "1000".to_string())
+        ],
+        SearchLocation::PostalCode("1234 ab".to_string()).query(1000)
+    );
+    assert_eq!(
+        vec![
+            ("latitude", "52.3731".to_string()),
+            ("longitude", "4.8922".to_string()),
+            ("radius", "500".to_string())
+        ],
+        SearchLocation::from(Coordinate::new(52.3731, 4.8922)).query(500)
+    );
+}
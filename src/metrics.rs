@@ -0,0 +1,224 @@
+use crate::data::InboxPackage;
+use crate::{http, LoggedIn, PostNL, Result};
+use chrono::Utc;
+use hyper::header::CONTENT_TYPE;
+use hyper::{Body, Request, Response, StatusCode};
+use once_cell::sync::Lazy;
+use prometheus::core::Collector;
+use prometheus::{Encoder, Gauge, IntCounter, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+/// The registry for the metrics of this crate, separate from the default registry of the application
+static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+fn register<C: Collector + Clone + 'static>(collector: C) -> C {
+    // the metric keeps working when it can't be registered, it's only missing from the output
+    if let Err(err) = REGISTRY.register(Box::new(collector.clone())) {
+        warn!(%err, "failed to register metric");
+    }
+    collector
+}
+
+pub(crate) static LOGIN_ATTEMPTS: Lazy<IntCounter> = Lazy::new(|| {
+    register(IntCounter::new("postnl_login_attempts_total", "Number of login attempts").unwrap())
+});
+
+pub(crate) static BLOCKED: Lazy<IntCounter> = Lazy::new(|| {
+    register(
+        IntCounter::new(
+            "postnl_blocked_total",
+            "Number of login attempts blocked by PostNL",
+        )
+        .unwrap(),
+    )
+});
+
+pub(crate) static TOKEN_REFRESHES: Lazy<IntCounter> = Lazy::new(|| {
+    register(IntCounter::new("postnl_token_refreshes_total", "Number of generated tokens").unwrap())
+});
+
+static FETCH_ERRORS: Lazy<IntCounter> = Lazy::new(|| {
+    register(
+        IntCounter::new(
+            "postnl_fetch_errors_total",
+            "Number of failed requests for the packages",
+        )
+        .unwrap(),
+    )
+});
+
+static PACKAGES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register(
+        IntGaugeVec::new(
+            Opts::new(
+                "postnl_packages",
+                "Number of packages in the inbox by delivery status",
+            ),
+            &["status"],
+        )
+        .unwrap(),
+    )
+});
+
+static ENROUTE: Lazy<IntGauge> = Lazy::new(|| {
+    register(IntGauge::new("postnl_packages_enroute", "Number of packages en route").unwrap())
+});
+
+static NEXT_DELIVERY: Lazy<Gauge> = Lazy::new(|| {
+    register(
+        Gauge::new(
+            "postnl_next_delivery_seconds",
+            "Seconds until the start of the next planned delivery window, NaN if no delivery is planned",
+        )
+        .unwrap(),
+    )
+});
+
+/// Update the package metrics from the current state of the inbox
+pub fn update(packages: &[InboxPackage]) {
+    PACKAGES.reset();
+    for package in packages {
+        PACKAGES
+            .with_label_values(&[&package.delivery.status.to_string()])
+            .inc();
+    }
+
+    let enroute = packages
+        .iter()
+        .filter_map(|package| package.enroute.as_ref());
+    ENROUTE.set(enroute.clone().count() as i64);
+
+    let now = Utc::now();
+    let next_delivery = enroute
+        .filter_map(|enroute| enroute.time_frame.from.or(enroute.time_frame.planned_from))
+        .filter(|from| *from > now)
+        .min();
+    NEXT_DELIVERY.set(
+        next_delivery
+            .map(|from| (from - now).num_seconds() as f64)
+            .unwrap_or(std::f64::NAN),
+    );
+}
+
+/// Get the metrics of this crate in the prometheus text format
+pub fn gather() -> String {
+    // ensure all metrics are registered, even when they haven't been touched yet
+    Lazy::force(&LOGIN_ATTEMPTS);
+    Lazy::force(&BLOCKED);
+    Lazy::force(&TOKEN_REFRESHES);
+    Lazy::force(&FETCH_ERRORS);
+    Lazy::force(&PACKAGES);
+    Lazy::force(&ENROUTE);
+    Lazy::force(&NEXT_DELIVERY);
+
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&REGISTRY.gather(), &mut buffer)
+        .expect("failed to encode metrics");
+    String::from_utf8(buffer).expect("metrics are valid utf8")
+}
+
+/// Http server exposing the metrics on `/metrics`
+pub struct MetricsServer {
+    client: PostNL<LoggedIn>,
+    interval: Duration,
+    last_update: Mutex<Option<Instant>>,
+}
+
+impl MetricsServer {
+    pub fn new(client: PostNL<LoggedIn>) -> Self {
+        MetricsServer {
+            client,
+            interval: Duration::from_secs(5 * 60),
+            last_update: Mutex::default(),
+        }
+    }
+
+    /// Set the minimum time between requests for the packages, defaults to 5 minutes
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        let server = Arc::new(self);
+
+        http::serve(addr, move |request| {
+            let server = server.clone();
+            async move { server.handle(request).await }
+        })
+        .await
+    }
+
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        if request.uri().path() != "/metrics" {
+            return Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::from("Not found"))
+                .unwrap();
+        }
+
+        self.refresh().await;
+
+        Response::builder()
+            .header(CONTENT_TYPE, TextEncoder::new().format_type())
+            .body(Body::from(gather()))
+            .unwrap()
+    }
+
+    /// Update the metrics from the api if the last update is older than the interval
+    async fn refresh(&self) {
+        let mut last_update = self.last_update.lock().await;
+
+        if matches!(*last_update, Some(updated) if updated.elapsed() < self.interval) {
+            return;
+        }
+
+        // errors are exposed by the error counter, the previous values are kept
+        match self.client.get_packages().await {
+            Ok(packages) => update(&packages),
+            Err(_) => FETCH_ERRORS.inc(),
+        }
+        last_update.replace(Instant::now());
+    }
+}
+
+#[test]
+fn test_update() {
+    use chrono::Duration;
+
+    let mut package = crate::data::fixture_package();
+    let from = Utc::now() + Duration::hours(1);
+    package.enroute.as_mut().unwrap().time_frame.from = Some(from);
+    let mut delivered = crate::data::fixture_package();
+    delivered.key = "delivered".to_string();
+    delivered.enroute = None;
+    delivered.delivery.status = crate::data::DeliveryStatus::Delivered;
+
+    update(&[package, delivered]);
+
+    assert_eq!(1, PACKAGES.with_label_values(&["Delivered"]).get());
+    assert_eq!(1, PACKAGES.with_label_values(&["EnrouteSpecific"]).get());
+    assert_eq!(1, ENROUTE.get());
+    assert!((NEXT_DELIVERY.get() - 3600.0).abs() < 5.0);
+    assert!(gather().contains("postnl_packages_enroute 1"));
+}
+
+#[test]
+fn test_separate_registry() {
+    // an application metric with the same name as one of ours
+    let application = IntCounter::new("postnl_blocked_total", "Application metric").unwrap();
+    prometheus::register(Box::new(application.clone())).unwrap();
+    application.inc_by(5);
+
+    BLOCKED.inc();
+    assert!(gather().contains("postnl_blocked_total 1"));
+    assert!(!gather().contains("Application metric"));
+    assert!(prometheus::gather()
+        .iter()
+        .all(|family| family.get_name() != "postnl_packages_enroute"));
+}
@@ -0,0 +1,247 @@
+use crate::data::InboxPackage;
+use crate::Result;
+use rumqttc::{AsyncClient, ConnectionError, MqttOptions, QoS};
+use serde_json::{json, Value};
+#[cfg(test)]
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// Publish the packages in the inbox as MQTT sensors, with Home Assistant discovery
+///
+/// The published sensors are only tracked in memory, sensors published by an earlier
+/// process for packages that have left the inbox since are not retracted.
+pub struct MqttPublisher {
+    client: AsyncClient,
+    discovery_prefix: String,
+    topic_prefix: String,
+    published: HashSet<String>,
+}
+
+impl MqttPublisher {
+    /// Connect to the broker, the connection is handled in a background task on the current runtime
+    pub async fn new(options: MqttOptions) -> Self {
+        let (client, mut event_loop) = AsyncClient::new(options, 16);
+
+        tokio::spawn(async move {
+            loop {
+                match event_loop.poll().await {
+                    Ok(_) => {}
+                    // all clients have been dropped
+                    Err(ConnectionError::RequestsDone) => break,
+                    // the event loop reconnects on the next poll after an error
+                    Err(_) => tokio::time::delay_for(Duration::from_secs(5)).await,
+                }
+            }
+        });
+
+        MqttPublisher {
+            client,
+            discovery_prefix: "homeassistant".to_string(),
+            topic_prefix: "postnl".to_string(),
+            published: HashSet::new(),
+        }
+    }
+
+    /// Set the prefix for discovery topics, defaults to `homeassistant`
+    pub fn discovery_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.discovery_prefix = prefix.into();
+        self
+    }
+
+    /// Set the prefix for state and attribute topics, defaults to `postnl`
+    pub fn topic_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.topic_prefix = prefix.into();
+        self
+    }
+
+    /// Publish the state of all packages and retract the sensors for packages no longer in the inbox
+    pub async fn publish(&mut self, packages: &[InboxPackage]) -> Result<()> {
+        let mut current = HashSet::new();
+
+        for package in packages {
+            let id = object_id(&package.key);
+
+            self.send(
+                &self.config_topic(&id),
+                self.config(&id, package).to_string(),
+            )
+            .await?;
+            self.send(&self.state_topic(&id), package.delivery.status.to_string())
+                .await?;
+            self.send(&self.attributes_topic(&id), attributes(package).to_string())
+                .await?;
+
+            current.insert(id);
+        }
+
+        for id in self.published.difference(&current) {
+            // an empty retained message removes the sensor
+            self.send(&self.config_topic(id), String::new()).await?;
+            self.send(&self.state_topic(id), String::new()).await?;
+            self.send(&self.attributes_topic(id), String::new()).await?;
+        }
+
+        self.published = current;
+        Ok(())
+    }
+
+    async fn send(&self, topic: &str, payload: String) -> Result<()> {
+        self.client
+            .publish(topic, QoS::AtLeastOnce, true, payload)
+            .await?;
+        Ok(())
+    }
+
+    fn config_topic(&self, id: &str) -> String {
+        format!("{}/sensor/postnl_{}/config", self.discovery_prefix, id)
+    }
+
+    fn state_topic(&self, id: &str) -> String {
+        format!("{}/{}/state", self.topic_prefix, id)
+    }
+
+    fn attributes_topic(&self, id: &str) -> String {
+        format!("{}/{}/attributes", self.topic_prefix, id)
+    }
+
+    fn config(&self, id: &str, package: &InboxPackage) -> Value {
+        json!({
+            "name": package.generated_titles.receiver,
+            "unique_id": format!("postnl_{}", id),
+            "state_topic": self.state_topic(id),
+            "json_attributes_topic": self.attributes_topic(id),
+            "icon": "mdi:package-variant-closed",
+            "device": {
+                "identifiers": ["postnl"],
+                "name": "PostNL",
+                "manufacturer": "PostNL",
+            },
+        })
+    }
+}
+
+fn attributes(package: &InboxPackage) -> Value {
+    let time_frame = package.enroute.as_ref().map(|enroute| {
+        let time_frame = &enroute.time_frame;
+        json!({
+            "type": time_frame.time_frame_type.to_string(),
+            "from": time_frame.from.or(time_frame.planned_from),
+            "to": time_frame.to.or(time_frame.planned_to),
+            "date": time_frame.date.or(time_frame.planned_date),
+        })
+    });
+    let delivery_location = package.delivery_location.as_ref().map(|location| {
+        json!({
+            "name": location.name,
+            "address": location.address.formatted,
+        })
+    });
+
+    json!({
+        "barcode": package.barcode,
+        "sender": package.generated_titles.receiver,
+        "time_frame": time_frame,
+        "delivery_location": delivery_location,
+    })
+}
+
+/// Topic and id safe version of the package key
+fn object_id(key: &str) -> String {
+    key.chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' => c,
+            'A'..='Z' => c.to_ascii_lowercase(),
+            _ => '_',
+        })
+        .collect()
+}
+
+#[test]
+fn test_object_id() {
+    assert_eq!(
+        "3sabcd1234567_nl_1234ab",
+        object_id("3SABCD1234567-NL-1234AB")
+    );
+    assert_eq!("a_b_c", object_id("a/b+c"));
+}
+
+#[test]
+fn test_attributes() {
+    let package = crate::data::fixture_package();
+
+    assert_eq!(
+        json!({
+            "barcode": "3SABCD1234567",
+            "sender": "Webshop B.V.",
+            "time_frame": {
+                "type": "Specific",
+                "from": "2020-05-21T12:30:00Z",
+                "to": "2020-05-21T14:30:00Z",
+                "date": "2020-05-21T00:00:00Z",
+            },
+            "delivery_location": {
+                "name": "Supermarkt Centrum",
+                "address": "Hoofdstraat 10, 1234AC Amsterdam",
+            },
+        }),
+        attributes(&package)
+    );
+}
+
+/// Collect the retained messages below `filter` until the broker goes quiet
+#[cfg(test)]
+async fn retained(options: MqttOptions, filter: &str) -> HashMap<String, String> {
+    use rumqttc::{Event, Incoming};
+
+    let (client, mut event_loop) = AsyncClient::new(options, 16);
+    client.subscribe(filter, QoS::AtLeastOnce).await.unwrap();
+
+    let mut messages = HashMap::new();
+    while let Ok(event) = tokio::time::timeout(Duration::from_secs(2), event_loop.poll()).await {
+        if let Event::Incoming(Incoming::Publish(publish)) = event.unwrap() {
+            if publish.retain {
+                let payload = String::from_utf8(publish.payload.to_vec()).unwrap();
+                messages.insert(publish.topic, payload);
+            }
+        }
+    }
+    messages
+}
+
+/// Needs a broker on localhost:1883, e.g. `docker run -p 1883:1883 eclipse-mosquitto:1.6`
+#[tokio::test]
+#[ignore]
+async fn test_publish_broker() {
+    let options = |id| MqttOptions::new(id, "localhost", 1883);
+    let package = crate::data::fixture_package();
+    let id = object_id(&package.key);
+
+    let mut publisher = MqttPublisher::new(options("postnl-test-publisher"))
+        .await
+        .discovery_prefix("postnl_test/discovery")
+        .topic_prefix("postnl_test/packages");
+    publisher
+        .publish(std::slice::from_ref(&package))
+        .await
+        .unwrap();
+    // give the background task time to deliver the messages
+    tokio::time::delay_for(Duration::from_secs(1)).await;
+
+    let messages = retained(options("postnl-test-subscriber"), "postnl_test/#").await;
+    let config: Value = serde_json::from_str(&messages[&publisher.config_topic(&id)]).unwrap();
+    assert_eq!(3, messages.len());
+    assert_eq!(format!("postnl_{}", id), config["unique_id"]);
+    assert_eq!(publisher.state_topic(&id), config["state_topic"]);
+    assert_eq!(
+        package.delivery.status.to_string(),
+        messages[&publisher.state_topic(&id)]
+    );
+    assert!(messages.contains_key(&publisher.attributes_topic(&id)));
+
+    publisher.publish(&[]).await.unwrap();
+    tokio::time::delay_for(Duration::from_secs(1)).await;
+
+    let messages = retained(options("postnl-test-subscriber"), "postnl_test/#").await;
+    assert!(messages.is_empty(), "{:?}", messages);
+}
@@ -0,0 +1,96 @@
+use crate::data::InboxPackage;
+use crate::{Error, LoggedIn, PostNL};
+use futures::future::join_all;
+
+/// A package from the combined inbox, with the labels of all accounts it was found in
+#[derive(Debug)]
+pub struct AccountPackage {
+    pub accounts: Vec<String>,
+    pub package: InboxPackage,
+}
+
+/// The combined inbox of all accounts
+#[derive(Debug, Default)]
+pub struct MultiAccountPackages {
+    pub packages: Vec<AccountPackage>,
+    /// The accounts for which the packages couldn't be retrieved
+    pub failures: Vec<(String, Error)>,
+}
+
+/// Client for multiple PostNL accounts at once
+#[derive(Default)]
+pub struct MultiAccount {
+    accounts: Vec<(String, PostNL<LoggedIn>)>,
+}
+
+impl MultiAccount {
+    pub fn new() -> Self {
+        MultiAccount::default()
+    }
+
+    /// Add a logged in account with a label to identify it
+    pub fn account(mut self, label: impl Into<String>, client: PostNL<LoggedIn>) -> Self {
+        self.accounts.push((label.into(), client));
+        self
+    }
+
+    /// The labels of all accounts
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.accounts.iter().map(|(label, _)| label.as_str())
+    }
+
+    /// Get the packages of all accounts concurrently
+    ///
+    /// Packages found in multiple accounts are only listed once, a failure for one account
+    /// doesn't prevent the packages from the other accounts being returned.
+    pub async fn get_packages(&self) -> MultiAccountPackages {
+        let results = join_all(
+            self.accounts
+                .iter()
+                .map(|(_, client)| client.get_packages()),
+        )
+        .await;
+
+        let mut combined = MultiAccountPackages::default();
+        for ((label, _), result) in self.accounts.iter().zip(results) {
+            match result {
+                Ok(packages) => merge(&mut combined.packages, label, packages),
+                Err(err) => combined.failures.push((label.clone(), err)),
+            }
+        }
+        combined
+    }
+}
+
+fn merge(combined: &mut Vec<AccountPackage>, label: &str, packages: Vec<InboxPackage>) {
+    for package in packages {
+        match combined
+            .iter_mut()
+            .find(|existing| existing.package.barcode == package.barcode)
+        {
+            Some(existing) => existing.accounts.push(label.to_string()),
+            None => combined.push(AccountPackage {
+                accounts: vec![label.to_string()],
+                package,
+            }),
+        }
+    }
+}
+
+#[test]
+fn test_merge() {
+    let package = crate::data::fixture_package();
+    let mut other = crate::data::fixture_package();
+    other.key = "other".to_string();
+    other.barcode = "3SOTHER".to_string();
+
+    let mut combined = Vec::new();
+    merge(&mut combined, "home", vec![package.clone()]);
+    merge(&mut combined, "office", vec![other, package]);
+
+    assert_eq!(2, combined.len());
+    assert_eq!(vec!["home", "office"], combined[0].accounts);
+    assert_eq!("3SABCD1234567", combined[0].package.barcode);
+    assert_eq!(vec!["office"], combined[1].accounts);
+    assert_eq!("3SOTHER", combined[1].package.barcode);
+}
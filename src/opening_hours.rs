@@ -0,0 +1,242 @@
+use crate::data::{Day, Hours, InboxDeliveryLocation, OpeningHours};
+use crate::time::TIMEZONE;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+
+impl From<Weekday> for Day {
+    fn from(weekday: Weekday) -> Self {
+        match weekday {
+            Weekday::Mon => Day::Monday,
+            Weekday::Tue => Day::Tuesday,
+            Weekday::Wed => Day::Wednesday,
+            Weekday::Thu => Day::Thursday,
+            Weekday::Fri => Day::Friday,
+            Weekday::Sat => Day::Saturday,
+            Weekday::Sun => Day::Sunday,
+        }
+    }
+}
+
+impl From<Day> for Weekday {
+    fn from(day: Day) -> Self {
+        match day {
+            Day::Monday => Weekday::Mon,
+            Day::Tuesday => Weekday::Tue,
+            Day::Wednesday => Weekday::Wed,
+            Day::Thursday => Weekday::Thu,
+            Day::Friday => Weekday::Fri,
+            Day::Saturday => Weekday::Sat,
+            Day::Sunday => Weekday::Sun,
+        }
+    }
+}
+
+impl Hours {
+    /// The opening time
+    pub fn opens(&self) -> NaiveTime {
+        self.from
+    }
+
+    /// The closing time, this can be before the opening time if the hours span midnight
+    pub fn closes(&self) -> NaiveTime {
+        self.to
+    }
+
+    /// Whether the opening hours continue until or past midnight, e.g. `22:00 - 02:00`
+    pub fn spans_midnight(&self) -> bool {
+        self.to <= self.from
+    }
+}
+
+impl OpeningHours {
+    pub fn day(&self) -> Day {
+        self.day
+    }
+
+    pub fn hours(&self) -> &[Hours] {
+        &self.hours
+    }
+
+    /// Whether the location is open at the given local time
+    ///
+    /// Hours spanning midnight are taken into account for the next day.
+    pub fn is_open_at<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> bool {
+        let day = Day::from(time.weekday());
+        let previous_day = Day::from(time.weekday().pred());
+        let time = time.time();
+
+        self.hours.iter().any(|hours| {
+            let open_today = day == self.day
+                && time >= hours.from
+                && (hours.spans_midnight() || time < hours.to);
+            let open_since_yesterday =
+                previous_day == self.day && hours.spans_midnight() && time < hours.to;
+            open_today || open_since_yesterday
+        })
+    }
+
+    /// The opening hours on the given date, empty if the date falls on another day
+    pub fn opening_on(&self, date: NaiveDate) -> &[Hours] {
+        if Day::from(date.weekday()) == self.day {
+            &self.hours
+        } else {
+            &[]
+        }
+    }
+
+    /// The opening hours for the current day in the Netherlands
+    pub fn opening_today(&self) -> &[Hours] {
+        self.opening_on(today())
+    }
+
+    /// The next time the location opens at or after the given local time
+    pub fn next_opening<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let start = time.naive_local().date();
+
+        (0..=7)
+            .map(|offset| start + Duration::days(offset))
+            .filter(|date| Day::from(date.weekday()) == self.day)
+            .flat_map(|date| {
+                self.hours.iter().filter_map(move |hours| {
+                    time.timezone()
+                        .from_local_datetime(&date.and_time(hours.from))
+                        .earliest()
+                })
+            })
+            .filter(|opening| opening >= time)
+            .min()
+    }
+}
+
+impl InboxDeliveryLocation {
+    /// Whether the location is open at the given local time
+    pub fn is_open_at<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> bool {
+        self.business_hours
+            .iter()
+            .any(|opening_hours| opening_hours.is_open_at(time))
+    }
+
+    /// The next time the location opens at or after the given local time
+    pub fn next_opening<Tz: TimeZone>(&self, time: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        self.business_hours
+            .iter()
+            .filter_map(|opening_hours| opening_hours.next_opening(time))
+            .min()
+    }
+
+    /// The opening hours on the given date
+    pub fn opening_on(&self, date: NaiveDate) -> Vec<&Hours> {
+        self.business_hours
+            .iter()
+            .flat_map(|opening_hours| opening_hours.opening_on(date))
+            .collect()
+    }
+
+    /// The opening hours for the current day in the Netherlands
+    pub fn opening_today(&self) -> Vec<&Hours> {
+        self.opening_on(today())
+    }
+}
+
+fn today() -> NaiveDate {
+    Utc::now().with_timezone(&TIMEZONE).naive_local().date()
+}
+
+#[test]
+fn test_day_weekday() {
+    for day in &[
+        Day::Monday,
+        Day::Tuesday,
+        Day::Wednesday,
+        Day::Thursday,
+        Day::Friday,
+        Day::Saturday,
+        Day::Sunday,
+    ] {
+        assert_eq!(*day, Day::from(Weekday::from(*day)));
+    }
+    assert_eq!(Weekday::Sun, Weekday::from(Day::Sunday));
+}
+
+#[test]
+fn test_is_open_at() {
+    // monday 08:00 - 12:00 and 13:00 - 20:00, saturday 22:00 - 02:00
+    let location = crate::data::fixture_package().delivery_location.unwrap();
+
+    // monday
+    assert!(!location.is_open_at(&Utc.ymd(2020, 5, 18).and_hms(7, 59, 0)));
+    assert!(location.is_open_at(&Utc.ymd(2020, 5, 18).and_hms(8, 0, 0)));
+    assert!(!location.is_open_at(&Utc.ymd(2020, 5, 18).and_hms(12, 30, 0)));
+    assert!(location.is_open_at(&Utc.ymd(2020, 5, 18).and_hms(19, 59, 0)));
+    assert!(!location.is_open_at(&Utc.ymd(2020, 5, 18).and_hms(20, 0, 0)));
+    // tuesday
+    assert!(!location.is_open_at(&Utc.ymd(2020, 5, 19).and_hms(9, 0, 0)));
+    // saturday night until sunday morning
+    assert!(!location.is_open_at(&Utc.ymd(2020, 5, 23).and_hms(1, 0, 0)));
+    assert!(location.is_open_at(&Utc.ymd(2020, 5, 23).and_hms(23, 0, 0)));
+    assert!(location.is_open_at(&Utc.ymd(2020, 5, 24).and_hms(1, 59, 0)));
+    assert!(!location.is_open_at(&Utc.ymd(2020, 5, 24).and_hms(2, 0, 0)));
+}
+
+#[test]
+fn test_next_opening() {
+    use chrono::FixedOffset;
+
+    let location = crate::data::fixture_package().delivery_location.unwrap();
+    let tz = FixedOffset::east(2 * 3600);
+
+    assert_eq!(
+        Some(tz.ymd(2020, 5, 18).and_hms(13, 0, 0)),
+        location.next_opening(&tz.ymd(2020, 5, 18).and_hms(12, 30, 0))
+    );
+    assert_eq!(
+        Some(tz.ymd(2020, 5, 18).and_hms(13, 0, 0)),
+        location.next_opening(&tz.ymd(2020, 5, 18).and_hms(13, 0, 0))
+    );
+    assert_eq!(
+        Some(tz.ymd(2020, 5, 23).and_hms(22, 0, 0)),
+        location.next_opening(&tz.ymd(2020, 5, 18).and_hms(21, 0, 0))
+    );
+    assert_eq!(
+        Some(tz.ymd(2020, 5, 25).and_hms(8, 0, 0)),
+        location.next_opening(&tz.ymd(2020, 5, 24).and_hms(1, 0, 0))
+    );
+
+    let closed = InboxDeliveryLocation {
+        business_hours: Vec::new(),
+        ..location
+    };
+    assert_eq!(
+        None,
+        closed.next_opening(&tz.ymd(2020, 5, 18).and_hms(12, 0, 0))
+    );
+}
+
+#[test]
+fn test_opening_on() {
+    let location = crate::data::fixture_package().delivery_location.unwrap();
+    let time = |hours: &&Hours| (hours.opens(), hours.closes());
+
+    assert_eq!(
+        vec![
+            (NaiveTime::from_hms(8, 0, 0), NaiveTime::from_hms(12, 0, 0)),
+            (NaiveTime::from_hms(13, 0, 0), NaiveTime::from_hms(20, 0, 0)),
+        ],
+        location
+            .opening_on(NaiveDate::from_ymd(2020, 5, 18))
+            .iter()
+            .map(time)
+            .collect::<Vec<_>>()
+    );
+    assert!(location
+        .opening_on(NaiveDate::from_ymd(2020, 5, 19))
+        .is_empty());
+    assert_eq!(
+        1,
+        location.business_hours[1]
+            .opening_on(NaiveDate::from_ymd(2020, 5, 23))
+            .len()
+    );
+    assert!(location.business_hours[1]
+        .opening_on(NaiveDate::from_ymd(2020, 5, 18))
+        .is_empty());
+}
@@ -0,0 +1,112 @@
+use crate::data::{FormattedStatus, FormattedStatusParams, Locale, Segment};
+
+impl FormattedStatus {
+    /// The short status as html, with `<time>` elements for dates and times
+    pub fn short_html(&self, locale: Locale) -> String {
+        html(self.short_segments(), locale)
+    }
+
+    /// The full status as html, with `<time>` elements for dates and times
+    pub fn body_html(&self, locale: Locale) -> String {
+        html(self.body_segments(), locale)
+    }
+
+    pub fn short_markdown(&self, locale: Locale) -> String {
+        markdown(self.short_segments(), locale)
+    }
+
+    pub fn body_markdown(&self, locale: Locale) -> String {
+        markdown(self.body_segments(), locale)
+    }
+}
+
+fn html<'a>(segments: impl Iterator<Item = Segment<'a>>, locale: Locale) -> String {
+    segments
+        .map(|segment| match segment {
+            Segment::Text(text) => escape_html(text).replace('\n', "<br>"),
+            Segment::Param(param) => format!(
+                r#"<time datetime="{}">{}</time>"#,
+                datetime_attribute(param),
+                escape_html(&param.render(locale))
+            ),
+        })
+        .collect()
+}
+
+fn markdown<'a>(segments: impl Iterator<Item = Segment<'a>>, locale: Locale) -> String {
+    segments
+        .map(|segment| match segment {
+            // a line ending in two spaces is rendered as a line break instead of a space
+            Segment::Text(text) => escape_markdown(text).replace('\n', "  \n"),
+            Segment::Param(param) => escape_markdown(&param.render(locale)),
+        })
+        .collect()
+}
+
+/// Machine readable value of a parameter, as used in the html `datetime` attribute
+fn datetime_attribute(param: &FormattedStatusParams) -> String {
+    match param {
+        FormattedStatusParams::Date(date) => date.format("%Y-%m-%d").to_string(),
+        FormattedStatusParams::DateAbs(date) => date.format("%Y-%m-%d").to_string(),
+        FormattedStatusParams::DateTime(date_time) => date_time.to_rfc3339(),
+        FormattedStatusParams::Time(time) => time.format("%H:%M:%S").to_string(),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.chars()
+        .fold(String::with_capacity(text.len()), |mut escaped, c| {
+            if "\\`*_{}[]()<>#+-.!|~".contains(c) {
+                escaped.push('\\');
+            }
+            escaped.push(c);
+            escaped
+        })
+}
+
+#[cfg(test)]
+fn test_status() -> FormattedStatus {
+    serde_json::from_value(serde_json::json!({
+        "title": "Bezorgd",
+        "body": "Bezorgd op {DateAbs:2019-08-27T12:28:12+02:00}\n<b>om</b> {time:2019-08-27T12:28:12+02:00} uur",
+        "short": "Verwacht {dateTime:2019-08-27T12:28:12+02:00} (*)",
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_html() {
+    let status = test_status();
+
+    assert_eq!(
+        "Bezorgd op <time datetime=\"2019-08-27\">dinsdag 27 augustus</time><br>\
+         &lt;b&gt;om&lt;/b&gt; <time datetime=\"12:28:12\">12:28</time> uur",
+        status.body_html(Locale::Nl)
+    );
+    assert_eq!(
+        "Verwacht <time datetime=\"2019-08-27T12:28:12+02:00\">27 August 2019 12:28</time> (*)",
+        status.short_html(Locale::En)
+    );
+}
+
+#[test]
+fn test_markdown() {
+    let status = test_status();
+
+    assert_eq!(
+        "Bezorgd op dinsdag 27 augustus  \n\\<b\\>om\\</b\\> 12:28 uur",
+        status.body_markdown(Locale::Nl)
+    );
+    assert_eq!(
+        "Verwacht 27 August 2019 12:28 \\(\\*\\)",
+        status.short_markdown(Locale::En)
+    );
+}
@@ -0,0 +1,201 @@
+use crate::data::{InboxDeliveryLocation, ReRoute, ReRouteAvailability};
+use crate::{api_url, Error, LoggedIn, PostNL, Result};
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use tracing::{field::Empty, instrument, Span};
+
+static REROUTE_URL: &str = "https://jouw.postnl.nl/web/api/default/reroute";
+
+/// A way the delivery of a package can be changed
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum ReRouteOption {
+    /// Deliver the package at a neighbour
+    Neighbour,
+    /// Deliver the package at a pickup point
+    PickupPoint {
+        location: Box<InboxDeliveryLocation>,
+    },
+    /// Deliver the package on a different date
+    OtherDate { date: DateTime<Utc> },
+}
+
+/// The chosen change for the delivery of a package
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum ReRouteChoice {
+    #[serde(rename_all = "camelCase")]
+    Neighbour {
+        house_number: String,
+        house_number_suffix: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    PickupPoint {
+        partner_id: String,
+        location_id: String,
+    },
+    OtherDate {
+        date: DateTime<Utc>,
+    },
+}
+
+impl ReRouteChoice {
+    /// Choose a pickup point from the available options
+    pub fn pickup_point(location: &InboxDeliveryLocation) -> Self {
+        ReRouteChoice::PickupPoint {
+            partner_id: location.partner_id.clone(),
+            location_id: location.location_id.clone(),
+        }
+    }
+}
+
+impl ReRouteAvailability {
+    /// Human readable explanation why a package can't be rerouted
+    pub fn reason(&self) -> &'static str {
+        match self {
+            ReRouteAvailability::AvailableAfterFirstAttempt => {
+                "rerouting is only possible after the first delivery attempt"
+            }
+            ReRouteAvailability::CustomerRelated => {
+                "rerouting is not allowed for this sender or recipient"
+            }
+            ReRouteAvailability::IncorrectStatus => {
+                "rerouting is not possible in the current status of the package"
+            }
+        }
+    }
+}
+
+/// Why a package can't be rerouted
+#[derive(Clone, Debug)]
+pub struct ReRouteUnavailable {
+    pub availability: ReRouteAvailability,
+    /// Explanation provided by PostNL
+    pub text: Option<String>,
+}
+
+impl fmt::Display for ReRouteUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.availability.reason())
+    }
+}
+
+impl ReRoute {
+    /// Get an error describing why rerouting isn't possible, if it isn't
+    pub fn check_available(&self) -> Result<()> {
+        if self.available {
+            Ok(())
+        } else {
+            Err(Error::ReRouteUnavailable(ReRouteUnavailable {
+                availability: self.availability.clone(),
+                text: self
+                    .unavailability
+                    .as_ref()
+                    .map(|unavailability| unavailability.text.clone()),
+            }))
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ReRouteResponse {
+    reroute: ReRoute,
+    #[serde(default)]
+    options: Vec<ReRouteOption>,
+}
+
+impl PostNL<LoggedIn> {
+    /// List the options to change the delivery of a package
+    #[instrument(skip(self), fields(status = Empty))]
+    pub async fn reroute_options(&self, key: &str) -> Result<Vec<ReRouteOption>> {
+        let response = self
+            .request(Method::GET, &api_url(REROUTE_URL, &[key]))
+            .await?
+            .send()
+            .await?;
+        Span::current().record("status", &response.status().as_u16());
+
+        let response: ReRouteResponse = response.error_for_status()?.json().await?;
+        response.reroute.check_available()?;
+        Ok(response.options)
+    }
+
+    /// Change the delivery of a package
+    #[instrument(skip(self, choice), fields(status = Empty))]
+    pub async fn reroute(&self, key: &str, choice: &ReRouteChoice) -> Result<()> {
+        let response = self
+            .request(Method::POST, &api_url(REROUTE_URL, &[key]))
+            .await?
+            .json(choice)
+            .send()
+            .await?;
+        Span::current().record("status", &response.status().as_u16());
+
+        response.error_for_status()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse_options() {
+    use serde_json::json;
+
+    let location = crate::data::fixture_package().delivery_location.unwrap();
+    let response: ReRouteResponse = serde_json::from_value(json!({
+        "reroute": {
+            "available": true,
+            "currentSelection": null,
+            "availability": "AvailableAfterFirstAttempt",
+            "unavailability": null,
+        },
+        "options": [
+            {"type": "Neighbour"},
+            {"type": "PickupPoint", "location": location},
+            {"type": "OtherDate", "date": "2020-05-22T00:00:00Z"},
+        ],
+    }))
+    .unwrap();
+
+    assert!(response.reroute.check_available().is_ok());
+    assert_eq!(
+        vec![
+            ReRouteOption::Neighbour,
+            ReRouteOption::PickupPoint {
+                location: Box::new(location.clone())
+            },
+            ReRouteOption::OtherDate {
+                date: "2020-05-22T00:00:00Z".parse().unwrap()
+            },
+        ],
+        response.options
+    );
+    assert_eq!(
+        json!({"type": "PickupPoint", "partnerId": "PNPNL-01", "locationId": "123456"}),
+        serde_json::to_value(ReRouteChoice::pickup_point(&location)).unwrap()
+    );
+}
+
+#[test]
+fn test_unavailable() {
+    let reroute = ReRoute {
+        available: false,
+        current_selection: None,
+        availability: ReRouteAvailability::IncorrectStatus,
+        unavailability: None,
+    };
+
+    let err = reroute.check_available().unwrap_err();
+    assert!(matches!(
+        err,
+        Error::ReRouteUnavailable(ReRouteUnavailable {
+            availability: ReRouteAvailability::IncorrectStatus,
+            ..
+        })
+    ));
+    assert_eq!(
+        "Package can't be rerouted: rerouting is not possible in the current status of the package",
+        err.to_string()
+    );
+}
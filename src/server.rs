@@ -0,0 +1,90 @@
+use crate::{http, ical, LoggedIn, PostNL, Result};
+use hyper::header::{CACHE_CONTROL, CONTENT_TYPE};
+use hyper::{Body, Method, Request, Response, StatusCode};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Http server serving a subscribable iCalendar feed of the expected deliveries
+pub struct IcalServer {
+    client: PostNL<LoggedIn>,
+    path: String,
+    ttl: Duration,
+    cache: Mutex<Option<(Instant, String)>>,
+}
+
+impl IcalServer {
+    pub fn new(client: PostNL<LoggedIn>) -> Self {
+        IcalServer {
+            client,
+            path: "/deliveries.ics".to_string(),
+            ttl: Duration::from_secs(15 * 60),
+            cache: Mutex::default(),
+        }
+    }
+
+    /// Set the path the calendar is served on, defaults to `/deliveries.ics`
+    ///
+    /// Since anyone who knows the url can see your packages, using a hard to guess path is recommended.
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Set how long a generated calendar is cached, defaults to 15 minutes
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        let server = Arc::new(self);
+
+        http::serve(addr, move |request| {
+            let server = server.clone();
+            async move { server.handle(request).await }
+        })
+        .await
+    }
+
+    async fn handle(&self, request: Request<Body>) -> Response<Body> {
+        if request.uri().path() != self.path {
+            return response(StatusCode::NOT_FOUND, "Not found");
+        }
+        if request.method() != Method::GET && request.method() != Method::HEAD {
+            return response(StatusCode::METHOD_NOT_ALLOWED, "Method not allowed");
+        }
+
+        match self.calendar().await {
+            Ok(calendar) => Response::builder()
+                .header(CONTENT_TYPE, "text/calendar; charset=utf-8")
+                .header(CACHE_CONTROL, format!("max-age={}", self.ttl.as_secs()))
+                .body(Body::from(calendar))
+                .unwrap(),
+            Err(err) => response(StatusCode::BAD_GATEWAY, err.to_string()),
+        }
+    }
+
+    /// Get the calendar, refreshing it from the api if the cached version is expired
+    async fn calendar(&self) -> Result<String> {
+        let mut cache = self.cache.lock().await;
+
+        match cache.as_ref() {
+            Some((created, calendar)) if created.elapsed() < self.ttl => Ok(calendar.clone()),
+            _ => {
+                let calendar = ical::calendar(&self.client.get_packages().await?);
+                cache.replace((Instant::now(), calendar.clone()));
+                Ok(calendar)
+            }
+        }
+    }
+}
+
+fn response(status: StatusCode, body: impl Into<Body>) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(body.into())
+        .unwrap()
+}
@@ -0,0 +1,298 @@
+use crate::data::{InboxObservation, InboxPackage};
+use crate::Result;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::types::Type;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+
+static SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS packages (
+    key TEXT PRIMARY KEY NOT NULL,
+    barcode TEXT NOT NULL,
+    sender TEXT,
+    effective_date TEXT NOT NULL,
+    first_seen TEXT NOT NULL,
+    last_seen TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS packages_sender ON packages (sender COLLATE NOCASE, effective_date);
+CREATE TABLE IF NOT EXISTS snapshots (
+    id INTEGER PRIMARY KEY,
+    key TEXT NOT NULL,
+    observed_at TEXT NOT NULL,
+    status TEXT NOT NULL,
+    data TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS snapshots_key ON snapshots (key, observed_at);
+CREATE TABLE IF NOT EXISTS observations (
+    barcode TEXT NOT NULL,
+    observation_date TEXT NOT NULL,
+    observation_code TEXT NOT NULL,
+    PRIMARY KEY (barcode, observation_date, observation_code)
+);
+";
+
+/// A package as it was seen at a specific time
+#[derive(Clone, Debug)]
+pub struct Snapshot {
+    pub observed_at: DateTime<Utc>,
+    pub package: InboxPackage,
+}
+
+/// Local history of all packages seen in the inbox, backed by SQLite
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(connection: Connection) -> Result<Self> {
+        connection.execute_batch(SCHEMA)?;
+        Ok(Store { connection })
+    }
+
+    /// Record the current state of the inbox
+    pub fn record(&mut self, packages: &[InboxPackage]) -> Result<()> {
+        self.record_at(packages, Utc::now())
+    }
+
+    /// Record the state of the inbox as observed at `observed_at`
+    ///
+    /// A new snapshot is only stored for packages that changed since the last recorded snapshot.
+    pub fn record_at(
+        &mut self,
+        packages: &[InboxPackage],
+        observed_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let observed_at = format_date(&observed_at);
+        let transaction = self.connection.transaction()?;
+
+        for package in packages {
+            let value = serde_json::to_value(package)?;
+            let data = value.to_string();
+
+            let last_data: Option<String> = transaction
+                .query_row(
+                    "SELECT data FROM snapshots WHERE key = ?1 ORDER BY id DESC LIMIT 1",
+                    params![package.key],
+                    |row| row.get(0),
+                )
+                .optional()?;
+
+            // compare the parsed json, the order of maps like `amounts` isn't stable
+            let changed = match last_data {
+                Some(last_data) => serde_json::from_str::<serde_json::Value>(&last_data)? != value,
+                None => true,
+            };
+
+            if changed {
+                transaction.execute(
+                    "INSERT INTO snapshots (key, observed_at, status, data) VALUES (?1, ?2, ?3, ?4)",
+                    params![
+                        package.key,
+                        observed_at,
+                        package.delivery.status.to_string(),
+                        data
+                    ],
+                )?;
+            }
+
+            transaction.execute(
+                "INSERT INTO packages (key, barcode, sender, effective_date, first_seen, last_seen, data)
+                    VALUES (?1, ?2, ?3, ?4, ?5, ?5, ?6)
+                    ON CONFLICT (key) DO UPDATE SET
+                        sender = excluded.sender,
+                        effective_date = excluded.effective_date,
+                        last_seen = excluded.last_seen,
+                        data = excluded.data",
+                params![
+                    package.key,
+                    package.barcode,
+                    sender_name(package),
+                    format_date(&package.effective_date),
+                    observed_at,
+                    data
+                ],
+            )?;
+
+            for observation in &package.all_observations {
+                transaction.execute(
+                    "INSERT OR IGNORE INTO observations (barcode, observation_date, observation_code)
+                        VALUES (?1, ?2, ?3)",
+                    params![
+                        package.barcode,
+                        format_date(&observation.observation_date),
+                        observation.observation_code
+                    ],
+                )?;
+            }
+        }
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    /// Get the last known state of all packages from a sender with an effective date after `since`
+    ///
+    /// The sender is matched case-insensitive.
+    pub fn packages_from_sender(
+        &self,
+        sender: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<InboxPackage>> {
+        let mut statement = self.connection.prepare(
+            "SELECT data FROM packages
+                WHERE sender = ?1 COLLATE NOCASE AND effective_date >= ?2
+                ORDER BY effective_date",
+        )?;
+        let rows = statement.query_map(params![sender, format_date(&since)], |row| {
+            row.get::<_, String>(0)
+        })?;
+
+        let mut packages = Vec::new();
+        for data in rows {
+            packages.push(serde_json::from_str(&data?)?);
+        }
+        Ok(packages)
+    }
+
+    /// Get all recorded snapshots of a package, oldest first
+    pub fn snapshots(&self, key: &str) -> Result<Vec<Snapshot>> {
+        let mut statement = self.connection.prepare(
+            "SELECT observed_at, data FROM snapshots WHERE key = ?1 ORDER BY observed_at, id",
+        )?;
+        let rows = statement.query_map(params![key], |row| {
+            Ok((
+                parse_date(&row.get::<_, String>(0)?, 0)?,
+                row.get::<_, String>(1)?,
+            ))
+        })?;
+
+        let mut snapshots = Vec::new();
+        for row in rows {
+            let (observed_at, data) = row?;
+            snapshots.push(Snapshot {
+                observed_at,
+                package: serde_json::from_str(&data)?,
+            });
+        }
+        Ok(snapshots)
+    }
+
+    /// Get the full status timeline for a barcode, oldest first
+    pub fn timeline(&self, barcode: &str) -> Result<Vec<InboxObservation>> {
+        let mut statement = self.connection.prepare(
+            "SELECT observation_date, observation_code FROM observations
+                WHERE barcode = ?1
+                ORDER BY observation_date",
+        )?;
+        let rows = statement.query_map(params![barcode], |row| {
+            Ok(InboxObservation {
+                observation_date: parse_date(&row.get::<_, String>(0)?, 0)?,
+                observation_code: row.get(1)?,
+            })
+        })?;
+
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+/// Note that the sender is stored in the "receiver" title for received packages
+fn sender_name(package: &InboxPackage) -> String {
+    package
+        .sender
+        .as_ref()
+        .and_then(|sender| sender.company_name.clone())
+        .unwrap_or_else(|| package.generated_titles.receiver.clone())
+}
+
+fn format_date(date: &DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn parse_date(date: &str, column: usize) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(date)
+        .map(|date| date.with_timezone(&Utc))
+        .map_err(|err| rusqlite::Error::FromSqlConversionFailure(column, Type::Text, Box::new(err)))
+}
+
+#[test]
+fn test_store_history() {
+    use crate::data::DeliveryStatus;
+    use chrono::TimeZone;
+
+    let mut store = Store::open_in_memory().unwrap();
+    let package = crate::data::fixture_package();
+
+    store
+        .record_at(&[package.clone()], Utc.ymd(2020, 5, 20).and_hms(10, 0, 0))
+        .unwrap();
+    store
+        .record_at(&[package.clone()], Utc.ymd(2020, 5, 20).and_hms(11, 0, 0))
+        .unwrap();
+
+    let mut delivered = package.clone();
+    delivered.delivery.status = DeliveryStatus::Delivered;
+    delivered.all_observations.push(InboxObservation {
+        observation_date: Utc.ymd(2020, 5, 21).and_hms(13, 0, 0),
+        observation_code: "I01".to_string(),
+    });
+    store
+        .record_at(&[delivered], Utc.ymd(2020, 5, 21).and_hms(14, 0, 0))
+        .unwrap();
+
+    let snapshots = store.snapshots(&package.key).unwrap();
+    assert_eq!(2, snapshots.len());
+    assert_eq!(
+        DeliveryStatus::Delivered,
+        snapshots[1].package.delivery.status
+    );
+
+    let timeline = store.timeline(&package.barcode).unwrap();
+    let codes: Vec<_> = timeline
+        .iter()
+        .map(|observation| observation.observation_code.as_str())
+        .collect();
+    assert_eq!(vec!["A01", "J05", "I01"], codes);
+
+    let from_sender = store
+        .packages_from_sender("webshop b.v.", Utc.ymd(2020, 1, 1).and_hms(0, 0, 0))
+        .unwrap();
+    assert_eq!(1, from_sender.len());
+    assert_eq!(DeliveryStatus::Delivered, from_sender[0].delivery.status);
+
+    let from_sender = store
+        .packages_from_sender("webshop b.v.", Utc.ymd(2020, 6, 1).and_hms(0, 0, 0))
+        .unwrap();
+    assert!(from_sender.is_empty());
+}
+
+#[test]
+fn test_store_unordered_amounts() {
+    use chrono::TimeZone;
+
+    let mut store = Store::open_in_memory().unwrap();
+    let key = crate::data::fixture_package().key;
+
+    for hour in 0..5 {
+        // every package gets a new hash map with its own iteration order
+        let mut package = crate::data::fixture_package();
+        for i in 0..8 {
+            package
+                .amounts
+                .insert(format!("amount{}", i), format!("{}.00", i));
+        }
+        store
+            .record_at(&[package], Utc.ymd(2020, 5, 20).and_hms(hour, 0, 0))
+            .unwrap();
+    }
+
+    assert_eq!(1, store.snapshots(&key).unwrap().len());
+}
@@ -0,0 +1,166 @@
+use crate::data::{TimeFrame, TimeFrameType};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use std::fmt;
+
+/// The timezone used by PostNL for delivery times
+pub const TIMEZONE: Tz = chrono_tz::Europe::Amsterdam;
+
+/// Convert a time to the Dutch local time
+pub fn to_local(time: &DateTime<Utc>) -> DateTime<Tz> {
+    time.with_timezone(&TIMEZONE)
+}
+
+/// The expected delivery window of a package in Dutch local time
+#[derive(Clone, Debug, PartialEq)]
+pub enum TimeWindow {
+    /// Delivery between two times
+    Between {
+        from: DateTime<Tz>,
+        to: DateTime<Tz>,
+    },
+    /// Delivery starting from a time, without a known end
+    From(DateTime<Tz>),
+    /// Delivery at an unknown time during the day
+    Day(NaiveDate),
+}
+
+impl TimeWindow {
+    /// The earliest expected delivery time, if known
+    pub fn start(&self) -> Option<DateTime<Tz>> {
+        match self {
+            TimeWindow::Between { from, .. } | TimeWindow::From(from) => Some(*from),
+            TimeWindow::Day(_) => None,
+        }
+    }
+
+    /// The latest expected delivery time, if known
+    pub fn end(&self) -> Option<DateTime<Tz>> {
+        match self {
+            TimeWindow::Between { to, .. } => Some(*to),
+            TimeWindow::From(_) | TimeWindow::Day(_) => None,
+        }
+    }
+
+    /// The local date of the delivery
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            TimeWindow::Between { from, .. } | TimeWindow::From(from) => from.naive_local().date(),
+            TimeWindow::Day(date) => *date,
+        }
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimeWindow::Between { from, to } => write!(
+                f,
+                "{} {} - {}",
+                from.format("%Y-%m-%d"),
+                from.format("%H:%M"),
+                to.format("%H:%M")
+            ),
+            TimeWindow::From(from) => write!(
+                f,
+                "{} from {}",
+                from.format("%Y-%m-%d"),
+                from.format("%H:%M")
+            ),
+            TimeWindow::Day(date) => write!(f, "{}", date.format("%Y-%m-%d")),
+        }
+    }
+}
+
+impl TimeFrame {
+    /// The start of the delivery window in local time, preferring the actual over the planned time
+    pub fn local_from(&self) -> Option<DateTime<Tz>> {
+        self.from.or(self.planned_from).as_ref().map(to_local)
+    }
+
+    /// The end of the delivery window in local time, preferring the actual over the planned time
+    pub fn local_to(&self) -> Option<DateTime<Tz>> {
+        self.to.or(self.planned_to).as_ref().map(to_local)
+    }
+
+    /// The local date of the delivery, preferring the actual over the planned date
+    pub fn local_date(&self) -> Option<NaiveDate> {
+        self.date
+            .or(self.planned_date)
+            .or(self.from)
+            .or(self.planned_from)
+            .map(|date| to_local(&date).naive_local().date())
+    }
+
+    /// The delivery window according to the type of the time frame
+    ///
+    /// Falls back to the delivery date if the times required by the type are missing.
+    pub fn window(&self) -> Option<TimeWindow> {
+        match (&self.time_frame_type, self.local_from(), self.local_to()) {
+            (TimeFrameType::Specific, Some(from), Some(to)) => {
+                Some(TimeWindow::Between { from, to })
+            }
+            (TimeFrameType::OnlyFromTime, Some(from), _) => Some(TimeWindow::From(from)),
+            _ => self.local_date().map(TimeWindow::Day),
+        }
+    }
+}
+
+#[test]
+fn test_window() {
+    use chrono::TimeZone;
+
+    let mut time_frame = crate::data::fixture_package().enroute.unwrap().time_frame;
+
+    // actual times are preferred over the planned times, summer time is UTC+2
+    let window = time_frame.window().unwrap();
+    assert_eq!(
+        TimeWindow::Between {
+            from: TIMEZONE.ymd(2020, 5, 21).and_hms(14, 30, 0),
+            to: TIMEZONE.ymd(2020, 5, 21).and_hms(16, 30, 0),
+        },
+        window
+    );
+    assert_eq!("2020-05-21 14:30 - 16:30", window.to_string());
+
+    time_frame.from = None;
+    time_frame.time_frame_type = TimeFrameType::OnlyFromTime;
+    assert_eq!(
+        Some(TimeWindow::From(
+            TIMEZONE.ymd(2020, 5, 21).and_hms(14, 0, 0)
+        )),
+        time_frame.window()
+    );
+
+    time_frame.time_frame_type = TimeFrameType::Specific;
+    time_frame.to = None;
+    time_frame.planned_to = None;
+    assert_eq!(
+        Some(TimeWindow::Day(NaiveDate::from_ymd(2020, 5, 21))),
+        time_frame.window()
+    );
+}
+
+#[test]
+fn test_winter_time() {
+    use chrono::TimeZone;
+
+    // a date sent as local midnight in winter time
+    let mut time_frame = crate::data::fixture_package().enroute.unwrap().time_frame;
+    time_frame.time_frame_type = TimeFrameType::WholeDay;
+    time_frame.date = Some(Utc.ymd(2020, 12, 1).and_hms(23, 0, 0));
+    time_frame.from = Some(Utc.ymd(2020, 12, 2).and_hms(13, 0, 0));
+
+    assert_eq!(
+        Some(TimeWindow::Day(NaiveDate::from_ymd(2020, 12, 2))),
+        time_frame.window()
+    );
+    assert_eq!(
+        "2020-12-02 14:00",
+        time_frame
+            .local_from()
+            .unwrap()
+            .format("%Y-%m-%d %H:%M")
+            .to_string()
+    );
+}
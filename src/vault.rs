@@ -0,0 +1,323 @@
+use crate::{Error, LoggedIn, PostNL, Result, Token};
+use chacha20poly1305::aead::{Aead, NewAead, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hmac::Hmac;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use zeroize::Zeroizing;
+
+const DEFAULT_ROUNDS: u32 = 100_000;
+/// Round counts accepted when opening a vault, to reject tampered files before deriving the key
+const MIN_ROUNDS: u32 = 10_000;
+const MAX_ROUNDS: u32 = 10_000_000;
+const VERIFICATION_TEXT: &[u8] = b"postnl-vault";
+const VERIFICATION_ACCOUNT: &str = "";
+
+/// The cached credentials for a single account
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub token: Option<Token>,
+    /// Any additional session data
+    pub blob: Option<Vec<u8>>,
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Session")
+            .field("token", &self.token)
+            .field("blob", &self.blob.as_ref().map(|_| "[redacted]"))
+            .finish()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+struct Entry {
+    nonce: String,
+    data: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct VaultFile {
+    salt: String,
+    rounds: u32,
+    verification: Entry,
+    entries: BTreeMap<String, Entry>,
+}
+
+/// Encrypted storage for the sessions of multiple accounts
+///
+/// The key is derived from the passphrase using PBKDF2-HMAC-SHA256 and every session is encrypted
+/// separately with ChaCha20-Poly1305, using the account name as associated data.
+pub struct Vault {
+    key: Zeroizing<[u8; 32]>,
+    salt: Vec<u8>,
+    rounds: u32,
+    verification: Entry,
+    entries: BTreeMap<String, Entry>,
+}
+
+impl Vault {
+    /// Create a new, empty, vault
+    pub fn create(passphrase: &str) -> Result<Self> {
+        Self::create_with_rounds(passphrase, DEFAULT_ROUNDS)
+    }
+
+    fn create_with_rounds(passphrase: &str, rounds: u32) -> Result<Self> {
+        let mut salt = vec![0; 16];
+        rand::thread_rng().fill_bytes(&mut salt);
+        let key = derive_key(passphrase, &salt, rounds);
+        let verification = encrypt(&key, VERIFICATION_ACCOUNT, VERIFICATION_TEXT)?;
+
+        Ok(Vault {
+            key,
+            salt,
+            rounds,
+            verification,
+            entries: BTreeMap::new(),
+        })
+    }
+
+    /// Open an existing vault, or create a new one if the file doesn't exist
+    pub fn open(path: impl AsRef<Path>, passphrase: &str) -> Result<Self> {
+        match fs::read(path) {
+            Ok(data) => Self::from_bytes(&data, passphrase),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Self::create(passphrase),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn from_bytes(data: &[u8], passphrase: &str) -> Result<Self> {
+        let file: VaultFile = serde_json::from_slice(data)?;
+        if !(MIN_ROUNDS..=MAX_ROUNDS).contains(&file.rounds) {
+            return Err(Error::VaultDecryption);
+        }
+        let salt = base64::decode(&file.salt).map_err(|_| Error::VaultDecryption)?;
+        let key = derive_key(passphrase, &salt, file.rounds);
+
+        if decrypt(&key, VERIFICATION_ACCOUNT, &file.verification)?.as_slice() != VERIFICATION_TEXT
+        {
+            return Err(Error::VaultDecryption);
+        }
+
+        Ok(Vault {
+            key,
+            salt,
+            rounds: file.rounds,
+            verification: file.verification,
+            entries: file.entries,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(&VaultFile {
+            salt: base64::encode(&self.salt),
+            rounds: self.rounds,
+            verification: self.verification.clone(),
+            entries: self.entries.clone(),
+        })?)
+    }
+
+    /// Save the vault, by writing to a temporary file next to `path` and renaming it into place
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+
+        fs::write(&temp_path, self.to_bytes()?)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    /// The names of all accounts stored in the vault
+    pub fn accounts(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    pub fn session(&self, account: &str) -> Result<Option<Session>> {
+        match self.entries.get(account) {
+            Some(entry) => Ok(Some(serde_json::from_slice(&decrypt(
+                &self.key, account, entry,
+            )?)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn set_session(&mut self, account: &str, session: &Session) -> Result<()> {
+        let plaintext = Zeroizing::new(serde_json::to_vec(session)?);
+        let entry = encrypt(&self.key, account, &plaintext)?;
+        self.entries.insert(account.to_string(), entry);
+        Ok(())
+    }
+
+    pub fn remove_session(&mut self, account: &str) {
+        self.entries.remove(account);
+    }
+
+    /// Store the current token of a logged in client
+    pub async fn store_token(&mut self, account: &str, client: &PostNL<LoggedIn>) -> Result<()> {
+        let mut session = self.session(account)?.unwrap_or_default();
+        session.token = Some(client.get_token().await?);
+        self.set_session(account, &session)
+    }
+
+    /// Load the stored token into a logged in client, returns `false` if no token is stored for the account
+    pub fn restore_token(&self, account: &str, client: &PostNL<LoggedIn>) -> Result<bool> {
+        match self.session(account)?.and_then(|session| session.token) {
+            Some(token) => {
+                client.set_token(token);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], rounds: u32) -> Zeroizing<[u8; 32]> {
+    let mut key = Zeroizing::new([0; 32]);
+    pbkdf2::pbkdf2::<Hmac<Sha256>>(passphrase.as_bytes(), salt, rounds as usize, &mut *key);
+    key
+}
+
+fn encrypt(key: &[u8; 32], account: &str, plaintext: &[u8]) -> Result<Entry> {
+    let mut nonce = [0; 12];
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let data = ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: plaintext,
+                aad: account.as_bytes(),
+            },
+        )
+        .map_err(|_| Error::VaultDecryption)?;
+
+    Ok(Entry {
+        nonce: base64::encode(&nonce),
+        data: base64::encode(&data),
+    })
+}
+
+fn decrypt(key: &[u8; 32], account: &str, entry: &Entry) -> Result<Zeroizing<Vec<u8>>> {
+    let nonce = base64::decode(&entry.nonce).map_err(|_| Error::VaultDecryption)?;
+    let data = base64::decode(&entry.data).map_err(|_| Error::VaultDecryption)?;
+    if nonce.len() != 12 {
+        return Err(Error::VaultDecryption);
+    }
+
+    ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(
+            Nonce::from_slice(&nonce),
+            Payload {
+                msg: &data,
+                aad: account.as_bytes(),
+            },
+        )
+        .map(Zeroizing::new)
+        .map_err(|_| Error::VaultDecryption)
+}
+
+#[cfg(test)]
+fn test_session() -> Session {
+    use crate::auth::{AccessToken, RefreshToken};
+    use chrono::Utc;
+
+    Session {
+        token: Some(Token {
+            access: AccessToken("access".to_string()),
+            id_token: RefreshToken("id".to_string()),
+            expires: Utc::now(),
+        }),
+        blob: Some(b"cookies".to_vec()),
+    }
+}
+
+#[test]
+fn test_vault_roundtrip() {
+    let mut vault = Vault::create_with_rounds("passphrase", MIN_ROUNDS).unwrap();
+    vault.set_session("home", &test_session()).unwrap();
+
+    let data = vault.to_bytes().unwrap();
+    assert!(!String::from_utf8_lossy(&data).contains("access"));
+
+    let vault = Vault::from_bytes(&data, "passphrase").unwrap();
+    assert_eq!(vec!["home"], vault.accounts().collect::<Vec<_>>());
+    let session = vault.session("home").unwrap().unwrap();
+    assert_eq!("access", session.token.unwrap().access.expose_secret());
+    assert_eq!(Some(b"cookies".to_vec()), session.blob);
+    assert!(vault.session("office").unwrap().is_none());
+}
+
+#[test]
+fn test_vault_wrong_passphrase() {
+    let mut vault = Vault::create_with_rounds("passphrase", MIN_ROUNDS).unwrap();
+    vault.set_session("home", &test_session()).unwrap();
+    let data = vault.to_bytes().unwrap();
+
+    assert!(matches!(
+        Vault::from_bytes(&data, "wrong"),
+        Err(Error::VaultDecryption)
+    ));
+}
+
+#[test]
+fn test_vault_tamper() {
+    let mut vault = Vault::create_with_rounds("passphrase", MIN_ROUNDS).unwrap();
+    vault.set_session("home", &test_session()).unwrap();
+    vault.set_session("office", &Session::default()).unwrap();
+
+    // flip a bit in the encrypted data
+    let mut file: VaultFile = serde_json::from_slice(&vault.to_bytes().unwrap()).unwrap();
+    let entry = file.entries.get_mut("home").unwrap();
+    let mut data = base64::decode(&entry.data).unwrap();
+    data[0] ^= 1;
+    entry.data = base64::encode(&data);
+    let vault = Vault::from_bytes(&serde_json::to_vec(&file).unwrap(), "passphrase").unwrap();
+    assert!(matches!(vault.session("home"), Err(Error::VaultDecryption)));
+
+    // swap the entries of two accounts
+    let mut file: VaultFile = serde_json::from_slice(&vault.to_bytes().unwrap()).unwrap();
+    let office = file.entries["office"].clone();
+    file.entries.insert("home".to_string(), office);
+    let vault = Vault::from_bytes(&serde_json::to_vec(&file).unwrap(), "passphrase").unwrap();
+    assert!(matches!(vault.session("home"), Err(Error::VaultDecryption)));
+}
+
+#[test]
+fn test_vault_rounds() {
+    let vault = Vault::create_with_rounds("passphrase", MIN_ROUNDS).unwrap();
+
+    for rounds in &[0, MIN_ROUNDS - 1, MAX_ROUNDS + 1, u32::MAX] {
+        let mut file: VaultFile = serde_json::from_slice(&vault.to_bytes().unwrap()).unwrap();
+        file.rounds = *rounds;
+        assert!(matches!(
+            Vault::from_bytes(&serde_json::to_vec(&file).unwrap(), "passphrase"),
+            Err(Error::VaultDecryption)
+        ));
+    }
+}
+
+#[test]
+fn test_vault_save() {
+    let path = std::env::temp_dir().join(format!("postnl-vault-{}.json", std::process::id()));
+    let mut vault = Vault::create_with_rounds("passphrase", MIN_ROUNDS).unwrap();
+    vault.set_session("home", &test_session()).unwrap();
+    vault.save(&path).unwrap();
+
+    let vault = Vault::open(&path, "passphrase").unwrap();
+    fs::remove_file(&path).unwrap();
+    assert_eq!(vec!["home"], vault.accounts().collect::<Vec<_>>());
+}
+
+#[test]
+fn test_session_debug() {
+    let debug = format!("{:?}", test_session());
+    assert!(!debug.contains("\"access\""));
+    assert!(!debug.contains("99, 111, 111"));
+    assert!(debug.contains("blob: Some(\"[redacted]\")"));
+}
@@ -0,0 +1,369 @@
+use crate::data::{diff, InboxPackage, PackageChange};
+use crate::{Error, LoggedIn, PostNL, Result};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use once_cell::sync::Lazy;
+use regex::{Captures, Regex};
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use sha2::Sha256;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+use tracing::warn;
+
+static PLACEHOLDER_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"\{\{([a-z]+)\}\}").unwrap());
+
+/// A http endpoint that package changes are posted to
+#[derive(Clone, Debug)]
+pub struct Webhook {
+    url: String,
+    template: Option<String>,
+    secret: Option<String>,
+}
+
+impl Webhook {
+    pub fn new(url: impl Into<String>) -> Self {
+        Webhook {
+            url: url.into(),
+            template: None,
+            secret: None,
+        }
+    }
+
+    /// Use a custom template for the posted json
+    ///
+    /// The placeholders `{{event}}`, `{{key}}`, `{{barcode}}`, `{{title}}` and `{{status}}` are replaced
+    /// by their json escaped value without surrounding quotes, so they can be used inside string literals.
+    /// `{{change}}` is replaced by the full change as json object.
+    ///
+    /// Without a template the change and the package it applies to are posted.
+    pub fn template(mut self, template: impl Into<String>) -> Self {
+        self.template = Some(template.into());
+        self
+    }
+
+    /// Sign the posted body with HMAC-SHA256, the signature is sent in the `X-PostNL-Signature-256` header
+    pub fn secret(mut self, secret: impl Into<String>) -> Self {
+        self.secret = Some(secret.into());
+        self
+    }
+
+    fn body(&self, change: &PackageChange, package: Option<&InboxPackage>) -> Result<String> {
+        let template = match &self.template {
+            Some(template) => template,
+            None => {
+                return Ok(serde_json::to_string(&WebhookEvent {
+                    event: event_name(change),
+                    change,
+                    package,
+                })?)
+            }
+        };
+
+        let title = package.map(|package| package.generated_titles.receiver.as_str());
+        let barcode = package.map(|package| package.barcode.as_str());
+        let status = package.map(|package| package.delivery.status.to_string());
+        let change_json = serde_json::to_string(change)?;
+
+        // all placeholders are replaced in a single pass, so substituted values are never expanded
+        Ok(PLACEHOLDER_REGEX
+            .replace_all(template, |captures: &Captures| match &captures[1] {
+                "event" => escape(event_name(change)),
+                "key" => escape(change.key()),
+                "barcode" => escape(barcode.unwrap_or_default()),
+                "title" => escape(title.unwrap_or_default()),
+                "status" => escape(status.as_deref().unwrap_or_default()),
+                "change" => change_json.clone(),
+                _ => captures[0].to_string(),
+            })
+            .into_owned())
+    }
+
+    fn signature(&self, body: &str) -> Option<String> {
+        let secret = self.secret.as_ref()?;
+        let mut mac =
+            Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("hmac accepts any key length");
+        mac.input(body.as_bytes());
+
+        Some(
+            mac.result()
+                .code()
+                .iter()
+                .fold(String::from("sha256="), |mut hex, byte| {
+                    let _ = write!(hex, "{:02x}", byte);
+                    hex
+                }),
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct WebhookEvent<'a> {
+    event: &'static str,
+    change: &'a PackageChange,
+    package: Option<&'a InboxPackage>,
+}
+
+#[derive(Serialize)]
+struct DeadLetter<'a> {
+    time: DateTime<Utc>,
+    url: &'a str,
+    body: &'a str,
+    error: &'a str,
+}
+
+/// Post changes in the inbox to a set of webhooks
+pub struct WebhookDispatcher {
+    client: reqwest::Client,
+    webhooks: Vec<Webhook>,
+    retries: u32,
+    retry_delay: Duration,
+    dead_letter_log: Option<PathBuf>,
+    previous: Option<Vec<InboxPackage>>,
+}
+
+impl WebhookDispatcher {
+    pub fn new() -> Result<Self> {
+        Ok(WebhookDispatcher {
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()?,
+            webhooks: Vec::new(),
+            retries: 3,
+            retry_delay: Duration::from_secs(1),
+            dead_letter_log: None,
+            previous: None,
+        })
+    }
+
+    pub fn webhook(mut self, webhook: Webhook) -> Self {
+        self.webhooks.push(webhook);
+        self
+    }
+
+    /// Set the number of retries for failed requests, the delay between retries doubles after every attempt
+    pub fn retries(mut self, retries: u32, retry_delay: Duration) -> Self {
+        self.retries = retries;
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Append events that could not be delivered to a file, as one json object per line
+    ///
+    /// Without a dead letter log, `dispatch` and `poll` return an error for undeliverable events.
+    pub fn dead_letter_log(mut self, path: impl Into<PathBuf>) -> Self {
+        self.dead_letter_log = Some(path.into());
+        self
+    }
+
+    /// Get the packages and post all changes since the previous poll
+    ///
+    /// The first poll only records the current state of the inbox.
+    pub async fn poll(&mut self, client: &PostNL<LoggedIn>) -> Result<Vec<PackageChange>> {
+        let packages = client.get_packages().await?;
+
+        let changes = match self.previous.as_ref() {
+            Some(previous) => diff(previous, &packages),
+            None => Vec::new(),
+        };
+
+        // the state is updated first, so a failure while dispatching doesn't resend delivered changes
+        let previous = self.previous.replace(packages.clone());
+        let mut result = Ok(());
+
+        for change in &changes {
+            let package = packages
+                .iter()
+                .chain(previous.iter().flatten())
+                .find(|package| package.key == change.key());
+            let dispatched = self.dispatch(change, package).await;
+            // keep dispatching the other changes, the first error is returned
+            if result.is_ok() {
+                result = dispatched;
+            }
+        }
+
+        result.map(|_| changes)
+    }
+
+    /// Post a single change to all webhooks
+    ///
+    /// Events that can't be delivered after retrying are written to the dead letter log. If no dead
+    /// letter log is configured the event is still posted to the other webhooks, and the first
+    /// failure is returned as `Error::WebhookDelivery`.
+    pub async fn dispatch(
+        &self,
+        change: &PackageChange,
+        package: Option<&InboxPackage>,
+    ) -> Result<()> {
+        let mut result = Ok(());
+
+        for webhook in &self.webhooks {
+            let body = webhook.body(change, package)?;
+
+            if let Err(error) = self.send(webhook, &body).await {
+                let written = self.write_dead_letter(&webhook.url, &body, &error)?;
+                if !written {
+                    warn!(url = %webhook.url, %error, "dropped undeliverable webhook event");
+                    if result.is_ok() {
+                        result = Err(Error::WebhookDelivery(format!(
+                            "{}: {}",
+                            webhook.url, error
+                        )));
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    async fn send(&self, webhook: &Webhook, body: &str) -> std::result::Result<(), String> {
+        let mut delay = self.retry_delay;
+        let mut attempt = 0;
+
+        loop {
+            let mut request = self
+                .client
+                .post(&webhook.url)
+                .header(CONTENT_TYPE, "application/json")
+                .body(body.to_string());
+            if let Some(signature) = webhook.signature(body) {
+                request = request.header("X-PostNL-Signature-256", signature);
+            }
+
+            let error = match request.send().await {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => format!("Unexpected response status {}", response.status()),
+                Err(err) => err.to_string(),
+            };
+
+            if attempt >= self.retries {
+                return Err(error);
+            }
+            attempt += 1;
+            tokio::time::delay_for(delay).await;
+            delay *= 2;
+        }
+    }
+
+    /// Returns `false` if no dead letter log is configured
+    fn write_dead_letter(&self, url: &str, body: &str, error: &str) -> Result<bool> {
+        if let Some(path) = &self.dead_letter_log {
+            let mut line = serde_json::to_string(&DeadLetter {
+                time: Utc::now(),
+                url,
+                body,
+                error,
+            })?;
+            line.push('\n');
+
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)?
+                .write_all(line.as_bytes())?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+}
+
+fn event_name(change: &PackageChange) -> &'static str {
+    match change {
+        PackageChange::Added { .. } => "added",
+        PackageChange::Removed { .. } => "removed",
+        PackageChange::DeliveryStatus { .. } => "deliveryStatus",
+        PackageChange::TimeFrame { .. } => "timeFrame",
+        PackageChange::DeliveryLocation { .. } => "deliveryLocation",
+        PackageChange::Observation { .. } => "observation",
+        PackageChange::FirstDeliveryAttemptFailed { .. } => "firstDeliveryAttemptFailed",
+    }
+}
+
+/// Escape a value for use inside a json string literal
+fn escape(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[test]
+fn test_template() {
+    use crate::data::DeliveryStatus;
+
+    let mut package = crate::data::fixture_package();
+    package.delivery.status = DeliveryStatus::Delivered;
+    let change = PackageChange::DeliveryStatus {
+        key: package.key.clone(),
+        old: DeliveryStatus::EnrouteSpecific,
+        new: DeliveryStatus::Delivered,
+    };
+    let webhook = Webhook::new("http://localhost").template(
+        r#"{"text": "{{title}} \"{{barcode}}\" is now {{status}}", "event": "{{event}}"}"#,
+    );
+
+    let body = webhook.body(&change, Some(&package)).unwrap();
+    assert_eq!(
+        r#"{"text": "Webshop B.V. \"3SABCD1234567\" is now Delivered", "event": "deliveryStatus"}"#,
+        body
+    );
+    let parsed: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!("deliveryStatus", parsed["event"]);
+}
+
+#[test]
+fn test_template_no_recursive_expansion() {
+    let mut package = crate::data::fixture_package();
+    package.generated_titles.receiver = "{{status}} {{change}} {{unknown}}".to_string();
+    let change = PackageChange::Added {
+        key: package.key.clone(),
+    };
+    let webhook = Webhook::new("http://localhost").template("{{title}}: {{status}} {{unknown}}");
+
+    assert_eq!(
+        "{{status}} {{change}} {{unknown}}: EnrouteSpecific {{unknown}}",
+        webhook.body(&change, Some(&package)).unwrap()
+    );
+}
+
+#[test]
+fn test_signature() {
+    let webhook = Webhook::new("http://localhost").secret("Jefe");
+
+    assert_eq!(
+        Some("sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843".to_string()),
+        webhook.signature("what do ya want for nothing?")
+    );
+    assert_eq!(None, Webhook::new("http://localhost").signature("body"));
+}
+
+#[tokio::test]
+async fn test_dispatch_undeliverable() {
+    let package = crate::data::fixture_package();
+    let change = PackageChange::Added {
+        key: package.key.clone(),
+    };
+    // nothing listens on port 1
+    let dispatcher = WebhookDispatcher::new()
+        .unwrap()
+        .webhook(Webhook::new("http://127.0.0.1:1/hook"))
+        .retries(0, Duration::from_millis(1));
+
+    assert!(matches!(
+        dispatcher.dispatch(&change, Some(&package)).await,
+        Err(Error::WebhookDelivery(_))
+    ));
+
+    let path = std::env::temp_dir().join(format!("postnl-dead-letter-{}.log", std::process::id()));
+    let dispatcher = dispatcher.dead_letter_log(&path);
+    dispatcher.dispatch(&change, Some(&package)).await.unwrap();
+
+    let log = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(1, log.lines().count());
+    assert!(log.contains("http://127.0.0.1:1/hook"));
+}